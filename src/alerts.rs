@@ -0,0 +1,94 @@
+//! Webhook notifications for conditions worth knowing about while results are
+//! still coming in, configured like so:
+//!
+//! ```toml
+//! [[alerts]]
+//! webhook = "https://example.com/hook"
+//! margin_below = 0.005
+//!
+//! [[alerts]]
+//! webhook = "https://example.com/hook"
+//! seats_at_least = { party = "Liberal", seats = 170 }
+//! ```
+
+use crate::output::{OutputSink, Webhook};
+use crate::{Party, Riding};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Error;
+
+/// A condition, and who to tell when it holds.
+#[derive(Debug, Deserialize)]
+pub struct Alert {
+    webhook: String,
+    #[serde(flatten)]
+    condition: Condition,
+}
+
+/// Something that may or may not be true of a set of results.
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// Some riding's margin of victory is below the given ratio.
+    MarginBelow(f64),
+    /// The given party has won at least this many seats.
+    SeatsAtLeast { party: Party, seats: usize },
+}
+
+impl Condition {
+    /// The ridings that make the condition hold, if it does.
+    fn evaluate(&self, ridings: &[Riding]) -> Option<Vec<String>> {
+        let names = |rs: Vec<&Riding>| rs.into_iter().map(|r| r.name.clone()).collect();
+
+        match self {
+            Condition::MarginBelow(ratio) => {
+                let close: Vec<_> = ridings
+                    .iter()
+                    .filter(|r| r.victory_margin() < *ratio)
+                    .collect();
+                (!close.is_empty()).then(|| names(close))
+            }
+            Condition::SeatsAtLeast { party, seats } => {
+                let won: Vec<_> = ridings.iter().filter(|r| r.was_winner(party)).collect();
+                (won.len() >= *seats).then(|| names(won))
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Notification<'a> {
+    year: usize,
+    condition: &'a Condition,
+    ridings: &'a [String],
+}
+
+/// Notify the relevant webhooks of every condition that holds. To avoid
+/// repeating themselves every time the data is refreshed, alerts only fire
+/// again once the set of ridings that triggered them has changed.
+pub fn check(
+    alerts: &[Alert],
+    year: usize,
+    ridings: &[Riding],
+    fired: &mut HashMap<usize, Vec<String>>,
+) -> Result<(), Error> {
+    for (i, alert) in alerts.iter().enumerate() {
+        match alert.condition.evaluate(ridings) {
+            None => {
+                fired.remove(&i);
+            }
+            Some(found) if fired.get(&i) != Some(&found) => {
+                let note = Notification {
+                    year,
+                    condition: &alert.condition,
+                    ridings: &found,
+                };
+                Webhook::new(&alert.webhook).write(&serde_json::to_string(&note)?)?;
+                fired.insert(i, found);
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Ridings that have gone by more than one name. A table of known renames is
+//! bundled with the tool, and users can add their own in the config file:
+//!
+//! ```toml
+//! aliases = [["Old Name", "New Name"]]
+//! ```
+
+use crate::fold;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+struct Bundled {
+    aliases: Vec<Vec<String>>,
+}
+
+/// Maps every known name of a riding to a single canonical one.
+pub struct Aliases {
+    canonical: HashMap<String, String>,
+}
+
+impl Aliases {
+    /// The bundled renames, plus any extra groups of names given by the user.
+    pub fn new(extra: &[Vec<String>]) -> Aliases {
+        // The bundled table is checked by the tests, so it always parses.
+        let bundled: Bundled = toml::from_str(include_str!("aliases.toml")).unwrap();
+        let mut canonical = HashMap::new();
+
+        for group in bundled.aliases.iter().chain(extra) {
+            if let Some(first) = group.first() {
+                let canon = key(first);
+
+                for name in group {
+                    canonical.insert(key(name), canon.clone());
+                }
+            }
+        }
+
+        Aliases { canonical }
+    }
+
+    /// A form of the riding's name that is the same under every alias.
+    pub fn key(&self, name: &str) -> String {
+        let k = key(name);
+        self.canonical.get(&k).cloned().unwrap_or(k)
+    }
+
+    /// Are these two names for the same riding?
+    pub fn same(&self, a: &str, b: &str) -> bool {
+        self.key(a) == self.key(b)
+    }
+}
+
+/// The English data spells em dashes as `--`, but other sources don't.
+fn key(name: &str) -> String {
+    fold(name).replace(['—', '–'], "--")
+}
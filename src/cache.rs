@@ -0,0 +1,174 @@
+//! On-disk memoization of rendered results, so that repeating an identical
+//! query against unchanged data is instant, and of each year's parsed polls,
+//! so that the CSVs needn't be parsed on every run.
+
+use crate::output::OutputSink;
+use crate::{Party, Poll};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Where the cached result for a query would live. The key should capture
+/// everything the result depends on: the analysis, its parameters, and the
+/// state of the data.
+pub fn path(key: &str) -> Option<PathBuf> {
+    let name: String = Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    dir().map(|d| d.join(name))
+}
+
+/// The cache directory, respecting `XDG_CACHE_HOME`.
+fn dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))
+        .map(|d| d.join("election"))
+}
+
+/// A sink that passes results through to another, keeping a copy in the
+/// cache as it goes.
+pub struct Recorder {
+    inner: Box<dyn OutputSink>,
+    path: PathBuf,
+}
+
+impl Recorder {
+    pub fn new(inner: Box<dyn OutputSink>, path: &Path) -> Recorder {
+        Recorder {
+            inner,
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl OutputSink for Recorder {
+    fn content_type(&mut self, content_type: &'static str) {
+        self.inner.content_type(content_type);
+    }
+
+    fn write(&mut self, body: &str) -> Result<(), Error> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, body)?;
+        self.inner.write(body)
+    }
+}
+
+/// A year's parsed polls, along with a stamp of the files they came from.
+#[derive(Serialize, Deserialize)]
+struct Parsed {
+    stamp: String,
+    polls: Vec<Entry>,
+}
+
+/// A [`Poll`] as stored. `Poll` itself reads its booleans as the `Y`/`N` of the
+/// CSVs, which a binary format can't accommodate.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    district: u32,
+    riding: String,
+    riding_fr: String,
+    number: String,
+    station: String,
+    void: bool,
+    not_held: bool,
+    party: Party,
+    last_name: String,
+    first_name: String,
+    middle_name: String,
+    votes: usize,
+    incumbent: bool,
+    elected: bool,
+    electors: usize,
+    rejected: usize,
+}
+
+impl From<&Poll> for Entry {
+    fn from(poll: &Poll) -> Entry {
+        Entry {
+            district: poll.district,
+            riding: poll.riding.clone(),
+            riding_fr: poll.riding_fr.clone(),
+            number: poll.number.clone(),
+            station: poll.station.clone(),
+            void: poll.void,
+            not_held: poll.not_held,
+            party: poll.party.clone(),
+            last_name: poll.last_name.clone(),
+            first_name: poll.first_name.clone(),
+            middle_name: poll.middle_name.clone(),
+            votes: poll.votes,
+            incumbent: poll.incumbent,
+            elected: poll.elected,
+            electors: poll.electors,
+            rejected: poll.rejected,
+        }
+    }
+}
+
+impl From<Entry> for Poll {
+    fn from(entry: Entry) -> Poll {
+        let poll = Poll {
+            district: entry.district,
+            riding: entry.riding,
+            riding_fr: entry.riding_fr,
+            number: entry.number,
+            station: entry.station,
+            void: entry.void,
+            not_held: entry.not_held,
+            party: entry.party,
+            last_name: entry.last_name,
+            first_name: entry.first_name,
+            middle_name: entry.middle_name,
+            votes: entry.votes,
+            incumbent: entry.incumbent,
+            elected: entry.elected,
+            electors: entry.electors,
+            rejected: entry.rejected,
+            ballots: Default::default(),
+        };
+
+        poll.classify()
+    }
+}
+
+/// The layout of [`Entry`], to be bumped whenever it changes so that older
+/// copies are never misread.
+const FORMAT: usize = 3;
+
+/// Where a year's parsed polls are kept.
+fn polls_path(year: usize) -> Option<PathBuf> {
+    dir().map(|d| d.join(format!("polls-{}.v{}.bin", year, FORMAT)))
+}
+
+/// The year's parsed polls, if there is a copy whose stamp matches.
+pub fn read_polls(year: usize, stamp: &str) -> Option<Vec<Poll>> {
+    let bytes = std::fs::read(polls_path(year)?).ok()?;
+    let parsed: Parsed = bincode::deserialize(&bytes).ok()?;
+
+    (parsed.stamp == stamp).then(|| parsed.polls.into_iter().map(Poll::from).collect())
+}
+
+/// Keep a copy of the year's parsed polls. The file is written in full before
+/// replacing any older copy, so a concurrent run never sees half of it.
+pub fn write_polls(year: usize, stamp: &str, polls: &[Poll]) -> Result<(), Error> {
+    let path =
+        polls_path(year).ok_or_else(|| Error::new(ErrorKind::NotFound, "No cache directory"))?;
+    let parsed = Parsed {
+        stamp: stamp.to_string(),
+        polls: polls.iter().map(Entry::from).collect(),
+    };
+    let bytes = bincode::serialize(&parsed).map_err(Error::other)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let part = path.with_extension("part");
+    std::fs::write(&part, bytes)?;
+    std::fs::rename(&part, &path)
+}
@@ -0,0 +1,91 @@
+//! Linking a candidate's runs in separate elections into a single career.
+
+use crate::aliases::Aliases;
+use crate::{fold, CandidateRun};
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Every run of one (presumed) person, oldest first.
+#[derive(Serialize, JsonSchema)]
+pub struct Career {
+    pub name: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub runs: Vec<CandidateRun>,
+}
+
+impl Career {
+    /// The most elections won in a row.
+    pub fn longest_streak(&self) -> usize {
+        self.runs
+            .iter()
+            .fold((0, 0), |(best, current), run| {
+                let current = if run.won { current + 1 } else { 0 };
+                (best.max(current), current)
+            })
+            .0
+    }
+
+    /// How many times the candidate lost before first winning, if they ever
+    /// did.
+    pub fn losses_before_first_win(&self) -> Option<usize> {
+        self.runs.iter().position(|run| run.won)
+    }
+
+    /// The biggest change in vote share from one attempt to the next, positive
+    /// or negative.
+    pub fn largest_swing(&self) -> Option<f64> {
+        self.runs
+            .windows(2)
+            .map(|w| w[1].ratio - w[0].ratio)
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+    }
+
+    /// The run that most recently joined this career.
+    fn latest(&self) -> &CandidateRun {
+        // A career is never created without a run.
+        self.runs.last().unwrap()
+    }
+
+    /// Could the given run plausibly be by the same person? The data carries
+    /// no candidate identifiers, so we assume that somebody with the same name
+    /// in a later election is the same person if they ran in the same riding
+    /// (perhaps under a new name) or under the same banner as last time.
+    fn continued_by(&self, run: &CandidateRun, aliases: &Aliases) -> bool {
+        let last = self.latest();
+
+        last.id.year < run.id.year
+            && (last.party == run.party
+                || last.id.corresponds_to(&run.id)
+                || aliases.same(&last.riding, &run.riding))
+    }
+}
+
+/// Group individual runs into careers. Somebody who only ran once still has a
+/// career, albeit a short one.
+pub fn link(mut runs: Vec<CandidateRun>, aliases: &Aliases) -> Vec<Career> {
+    runs.sort_by_cached_key(|r| (fold(&r.last_name), fold(&r.first_name), r.id.year));
+
+    runs.into_iter()
+        .group_by(|r| (fold(&r.last_name), fold(&r.first_name)))
+        .into_iter()
+        .flat_map(|(_, group)| {
+            let mut careers: Vec<Career> = Vec::new();
+
+            for run in group {
+                match careers.iter_mut().find(|c| c.continued_by(&run, aliases)) {
+                    Some(career) => career.runs.push(run),
+                    None => careers.push(Career {
+                        name: run.name.clone(),
+                        first_name: run.first_name.clone(),
+                        last_name: run.last_name.clone(),
+                        runs: vec![run],
+                    }),
+                }
+            }
+
+            careers
+        })
+        .collect()
+}
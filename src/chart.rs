@@ -0,0 +1,398 @@
+//! `election chart KIND [--year YEAR]`: results drawn as SVG, ready to publish
+//! as they are. Like any result, they go wherever `--output` or `--sink` say.
+//!
+//! - `seats`: the House of Commons as a half-disc of seats, each party's
+//!   together and in the colour it's usually drawn in, from the left of the
+//!   chamber to the right.
+//! - `shares`: each party's share of the vote as a bar.
+//!
+//! Either can be limited to a single `--province`, or drawn in the terminal
+//! instead with `--terminal`, as bars of Unicode blocks.
+//!
+//! With the `png` feature, there are also PNG charts, written to `--output`:
+//!
+//! - `margins`: how many ridings were won by how much.
+//! - `swing`: the change in each major party's share of the vote since the
+//!   election given with `--from`.
+//! - `trend`: the seats each major party won in every election.
+
+use crate::config::Settings;
+use crate::format::{escape, label, paint};
+use crate::output::Output;
+#[cfg(feature = "png")]
+use crate::png;
+use crate::{blocks_of, totals_of, Party, Poll, Province, SeatBlock, VoteCount, YEARS};
+use clap::{ArgEnum, Clap};
+use std::cmp::Reverse;
+use std::f64::consts::PI;
+use std::fmt::Write;
+use std::io::Error;
+#[cfg(feature = "png")]
+use std::io::ErrorKind;
+use std::path::Path;
+
+#[derive(Clap, Debug)]
+pub struct ChartArgs {
+    /// What to draw.
+    #[clap(arg_enum)]
+    kind: Kind,
+
+    /// The earlier election to measure swing from.
+    #[clap(long, possible_values = YEARS, required_if_eq("kind", "swing"))]
+    from: Option<usize>,
+
+    /// Only draw the ridings of the given province.
+    #[clap(long, arg_enum)]
+    province: Option<Province>,
+
+    /// Draw seats or shares as bars in the terminal, instead of as SVG.
+    #[clap(long)]
+    terminal: bool,
+}
+
+impl ChartArgs {
+    /// The years whose results the chart reads for itself, given the one
+    /// asked for with `--year` and every one there's data for. The rest only
+    /// draw the polls they're handed.
+    pub fn years(&self, year: usize, available: impl Iterator<Item = usize>) -> Option<Vec<usize>> {
+        match self.kind {
+            Kind::Swing => Some(vec![self.from.unwrap_or(year), year]),
+            Kind::Trend => Some(available.collect()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum Kind {
+    Seats,
+    Shares,
+    Margins,
+    Swing,
+    Trend,
+}
+
+/// The radius of the half-disc, in pixels.
+const RADIUS: f64 = 300.0;
+
+/// Space left around the drawing, in pixels.
+const MARGIN: f64 = 20.0;
+
+/// How far out the innermost row of seats sits, as a share of the radius.
+const INNER: f64 = 0.4;
+
+/// Draw the chart given in `args` of the year's polls, already fused. PNGs
+/// can only be written to the `file` given with `--output`.
+#[cfg_attr(not(feature = "png"), allow(unused_variables))]
+pub fn chart(
+    settings: &Settings,
+    args: &ChartArgs,
+    year: usize,
+    mut polls: Vec<Poll>,
+    file: Option<&Path>,
+    out: &mut Output,
+) -> Result<(), Error> {
+    if let Some(province) = args.province {
+        polls.retain(|p| Province::from_district(p.district) == Some(province));
+    }
+
+    let place = args
+        .province
+        .map(|p| label(&p))
+        .unwrap_or_else(|| "Canada".to_string());
+
+    let svg = match args.kind {
+        Kind::Seats if args.terminal => {
+            let rows: Vec<Bar> = blocks_of(polls)
+                .into_iter()
+                .map(|b| (b.party, b.seats as f64, b.seats.to_string()))
+                .collect();
+            let title = format!("{}, {}: seats", place, year);
+            return out.document("text/plain", &bars(&title, &rows, out.coloured()));
+        }
+        Kind::Seats => seats(&blocks_of(polls)),
+        Kind::Shares => {
+            let mut parties = totals_of(polls).parties;
+            parties.sort_by_key(|p| Reverse(p.votes));
+            let title = format!("{}, {}: share of the vote", place, year);
+
+            if args.terminal {
+                let rows: Vec<Bar> = parties
+                    .into_iter()
+                    .map(|p| (p.party, p.ratio, format!("{:.1}%", p.ratio * 100.0)))
+                    .collect();
+                return out.document("text/plain", &bars(&title, &rows, out.coloured()));
+            }
+
+            shares(&title, &parties)
+        }
+        #[cfg(feature = "png")]
+        Kind::Margins => {
+            let title = format!("Margins of victory, {}", year);
+            return image(file, |part| png::margins(&title, polls, part));
+        }
+        #[cfg(feature = "png")]
+        Kind::Swing => {
+            let from = args.from.unwrap_or(year);
+            return image(file, |part| png::swing(settings, from, year, part));
+        }
+        #[cfg(feature = "png")]
+        Kind::Trend => return image(file, |part| png::trend(settings, part)),
+        #[cfg(not(feature = "png"))]
+        Kind::Margins | Kind::Swing | Kind::Trend => {
+            let msg = "PNG charts need election to be built with `--features png`";
+            return Err(Error::other(msg));
+        }
+    };
+
+    out.document("image/svg+xml", &svg)
+}
+
+/// Draw a PNG, which can't be printed, alongside `file` and then move it into
+/// place, so that it's never seen half-drawn. The drawing keeps the `.png`
+/// extension that tells plotters how to encode it.
+#[cfg(feature = "png")]
+fn image<F>(file: Option<&Path>, draw: F) -> Result<(), Error>
+where
+    F: FnOnce(&Path) -> Result<(), Error>,
+{
+    let file =
+        file.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "PNG charts need --output FILE"))?;
+    let part = file.with_extension("part.png");
+
+    draw(&part)?;
+    std::fs::rename(&part, file)
+}
+
+/// The parliament chart, with a legend of each party's seats beneath.
+fn seats(blocks: &[SeatBlock]) -> String {
+    let total: usize = blocks.iter().map(|b| b.seats).sum();
+    let (positions, dot) = seat_positions(total);
+    let colours = blocks
+        .iter()
+        .flat_map(|b| std::iter::repeat_n(b.colour, b.seats));
+
+    let (cx, cy) = (RADIUS + MARGIN, RADIUS + MARGIN);
+    let legend = 24.0 * blocks.len() as f64;
+    let width = 2.0 * (RADIUS + MARGIN);
+    let height = cy + MARGIN + legend;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">",
+        w = width,
+        h = height
+    );
+
+    for ((x, y), colour) in positions.iter().zip(colours) {
+        let _ = writeln!(
+            svg,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\"/>",
+            cx + x * RADIUS,
+            cy - y * RADIUS,
+            dot * RADIUS,
+            colour
+        );
+    }
+
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"32\">{}</text>",
+        cx,
+        cy - 8.0,
+        total
+    );
+
+    for (i, b) in blocks.iter().enumerate() {
+        let y = cy + MARGIN + 24.0 * i as f64;
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"16\" height=\"16\" fill=\"{}\"/>",
+            MARGIN, y, b.colour
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" font-size=\"14\">{}: {}</text>",
+            MARGIN + 24.0,
+            y + 13.0,
+            escape(&label(&b.party)),
+            b.seats
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The height of each bar of a bar chart, with the space between them, in
+/// pixels.
+const ROW: f64 = 28.0;
+
+/// How much room is left for party names, in pixels.
+const NAMES: f64 = 220.0;
+
+/// How wide the longest bar is, in pixels.
+const BAR: f64 = 340.0;
+
+/// A horizontal bar per party, in the order given, longest scaled to fit.
+fn shares(title: &str, parties: &[VoteCount]) -> String {
+    let most = parties.iter().map(|p| p.ratio).fold(0.0, f64::max);
+    let width = MARGIN + NAMES + BAR + 80.0;
+    let height = 2.0 * MARGIN + ROW * (parties.len() + 1) as f64;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">",
+        w = width,
+        h = height
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{}\" font-size=\"18\">{}</text>",
+        MARGIN,
+        MARGIN + 16.0,
+        escape(title)
+    );
+
+    for (i, p) in parties.iter().enumerate() {
+        let y = MARGIN + ROW * (i + 1) as f64;
+        let length = if most > 0.0 {
+            BAR * p.ratio / most
+        } else {
+            0.0
+        };
+
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" font-size=\"14\" text-anchor=\"end\">{}</text>",
+            MARGIN + NAMES - 8.0,
+            y + 16.0,
+            escape(&label(&p.party))
+        );
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"{:.2}\" height=\"{}\" fill=\"{}\"/>",
+            MARGIN + NAMES,
+            y + 4.0,
+            length,
+            ROW - 8.0,
+            p.party.colour()
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.2}\" y=\"{}\" font-size=\"14\">{:.1}%</text>",
+            MARGIN + NAMES + length + 6.0,
+            y + 16.0,
+            p.ratio * 100.0
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A party, the length of its bar, and the figure written after it.
+type Bar = (Party, f64, String);
+
+/// How many columns the longest bar of a terminal chart spans.
+const COLUMNS: usize = 40;
+
+/// Horizontal bars drawn with Unicode blocks, to eighths of a column, in each
+/// party's colour if asked.
+fn bars(title: &str, rows: &[Bar], colour: bool) -> String {
+    const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+    let most = rows.iter().map(|(_, n, _)| *n).fold(0.0, f64::max);
+    let names: Vec<String> = rows.iter().map(|(p, _, _)| label(p)).collect();
+    let width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+
+    let mut out = format!("{}\n\n", title);
+    for ((party, n, figure), name) in rows.iter().zip(names) {
+        let eighths = if most > 0.0 {
+            (n / most * (COLUMNS * 8) as f64).round() as usize
+        } else {
+            0
+        };
+        let bar = format!("{}{}", "█".repeat(eighths / 8), EIGHTHS[eighths % 8]);
+        let bar = if colour {
+            paint(&bar, &party.rgb())
+        } else {
+            bar
+        };
+        let pad = width - name.chars().count();
+        let _ = writeln!(out, "{}{} {} {}", " ".repeat(pad), name, bar, figure);
+    }
+
+    out
+}
+
+/// Where each of the given number of seats sits on a half-disc of radius 1
+/// centred on the origin, ordered from the left of the chamber to the right,
+/// along with the radius of each seat's dot.
+///
+/// Seats are laid out in as few concentric rows as will hold them, each row
+/// getting a share of the seats in proportion to its length.
+fn seat_positions(seats: usize) -> (Vec<(f64, f64)>, f64) {
+    if seats == 0 {
+        return (Vec::new(), 0.0);
+    }
+
+    let radii = |rows: usize| -> Vec<f64> {
+        let gap = (1.0 - INNER) / rows as f64;
+        (0..rows).map(|i| INNER + gap * (i as f64 + 0.5)).collect()
+    };
+    // How many dots fit along a row, a row's gap apart.
+    let capacity = |rows: usize| -> usize {
+        let gap = (1.0 - INNER) / rows as f64;
+        radii(rows)
+            .iter()
+            .map(|r| (PI * r / gap).floor() as usize + 1)
+            .sum()
+    };
+    let rows = (1..=seats).find(|r| capacity(*r) >= seats).unwrap_or(seats);
+    let radii = radii(rows);
+    let gap = (1.0 - INNER) / rows as f64;
+
+    // Split the seats between the rows by the largest remainder.
+    let length: f64 = radii.iter().sum();
+    let shares: Vec<f64> = radii.iter().map(|r| seats as f64 * r / length).collect();
+    let mut counts: Vec<usize> = shares.iter().map(|s| s.floor() as usize).collect();
+    let mut remainders: Vec<usize> = (0..rows).collect();
+    remainders.sort_by(|a, b| {
+        (shares[*b] - shares[*b].floor()).total_cmp(&(shares[*a] - shares[*a].floor()))
+    });
+    for i in remainders
+        .into_iter()
+        .take(seats - counts.iter().sum::<usize>())
+    {
+        counts[i] += 1;
+    }
+
+    let mut positions: Vec<(f64, f64)> = radii
+        .iter()
+        .zip(counts)
+        .flat_map(|(r, n)| {
+            (0..n).map(move |j| {
+                let angle = if n > 1 {
+                    PI * (1.0 - j as f64 / (n - 1) as f64)
+                } else {
+                    PI / 2.0
+                };
+                (angle, *r)
+            })
+        })
+        .collect();
+
+    // Left to right is from an angle of π down to 0. Within a sliver of the
+    // chamber, the inner seats come first.
+    positions.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.total_cmp(&b.1)));
+
+    let seats = positions
+        .into_iter()
+        .map(|(angle, r)| (r * angle.cos(), r * angle.sin()))
+        .collect();
+
+    (seats, gap * 0.4)
+}
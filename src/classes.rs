@@ -0,0 +1,53 @@
+//! User-supplied classifications of ridings (say, urban, suburban and rural),
+//! read from a CSV whose rows give a `class` along with either the `district`
+//! number or the `riding` name.
+
+use crate::fold;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Error;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct Row {
+    district: Option<u32>,
+    riding: Option<String>,
+    class: String,
+}
+
+/// The class of each riding that the classification file mentions.
+pub struct Classes {
+    by_district: HashMap<u32, String>,
+    by_name: HashMap<String, String>,
+}
+
+impl Classes {
+    pub fn load(path: &Path) -> Result<Classes, Error> {
+        let mut by_district = HashMap::new();
+        let mut by_name = HashMap::new();
+
+        for row in csv::Reader::from_path(path)?.deserialize::<Row>() {
+            let row = row?;
+
+            if let Some(d) = row.district {
+                by_district.insert(d, row.class.clone());
+            }
+            if let Some(name) = row.riding {
+                by_name.insert(fold(&name), row.class);
+            }
+        }
+
+        Ok(Classes {
+            by_district,
+            by_name,
+        })
+    }
+
+    /// The class of a riding, preferring a match on its district number.
+    pub fn of(&self, district: u32, name: &str) -> Option<&str> {
+        self.by_district
+            .get(&district)
+            .or_else(|| self.by_name.get(&fold(name)))
+            .map(|c| c.as_str())
+    }
+}
@@ -0,0 +1,138 @@
+//! User configuration, read from `~/.config/election/config.toml`: presets,
+//! riding aliases, alerts, and defaults for the most common flags.
+
+use crate::alerts::Alert;
+use crate::jurisdiction::Kind;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+/// Presets that ship with the tool. Each maps a name to the arguments it
+/// stands for.
+const BUILTIN_PRESETS: &[(&str, &[&str])] = &[
+    ("closest-races-2021", &["margins", "--year", "2021"]),
+    ("closest-races-2019", &["margins", "--year", "2019"]),
+    ("closest-races-2015", &["margins", "--year", "2015"]),
+    ("totals-2021", &["totals", "--year", "2021"]),
+    ("totals-2019", &["totals", "--year", "2019"]),
+    ("totals-2015", &["totals", "--year", "2015"]),
+    ("conppc-2019", &["combine", "con,ppc", "--year", "2019"]),
+];
+
+/// The contents of the user's config file.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// User-defined presets, which take precedence over the built-in ones.
+    #[serde(default)]
+    presets: HashMap<String, Vec<String>>,
+    /// Extra groups of names that refer to the same riding.
+    #[serde(default)]
+    pub aliases: Vec<Vec<String>>,
+    /// Notifications to send while in watch mode.
+    #[serde(default)]
+    pub alerts: Vec<Alert>,
+    /// Options to use whenever their flags aren't given.
+    #[serde(default)]
+    pub defaults: Defaults,
+}
+
+/// The `[defaults]` table of the config file. Each stands in for the flag of
+/// the same name, which always wins when given.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Defaults {
+    data_dir: Option<PathBuf>,
+    year: Option<usize>,
+    /// Only used when neither --format, --json nor --output says otherwise.
+    pub format: Option<String>,
+    lang: Option<String>,
+    jurisdiction: Option<String>,
+    #[serde(default)]
+    pub no_color: bool,
+    classes: Option<PathBuf>,
+    corrections: Option<PathBuf>,
+    redistribution: Option<PathBuf>,
+}
+
+impl Defaults {
+    /// The flags these stand in for, by name, with the values to give them
+    /// when they're given neither on the command line nor in the environment.
+    pub fn flags(&self) -> Vec<(&'static str, String)> {
+        let paths = [
+            ("data-dir", &self.data_dir),
+            ("classes", &self.classes),
+            ("corrections", &self.corrections),
+            ("redistribution", &self.redistribution),
+        ];
+
+        paths
+            .iter()
+            .filter_map(|(flag, path)| path.as_ref().map(|p| (*flag, p.display().to_string())))
+            .chain(self.year.map(|y| ("year", y.to_string())))
+            .chain(self.lang.clone().map(|l| ("lang", l)))
+            .chain(self.jurisdiction.clone().map(|j| ("jurisdiction", j)))
+            .collect()
+    }
+}
+
+/// Everything the loaders need to know besides the year: where the data lives,
+/// the files read alongside it, and how results are shown. These are resolved
+/// once from the flags, the environment and the config file, in that order of
+/// precedence.
+#[derive(Debug, Default, Clone)]
+pub struct Settings {
+    pub jurisdiction: Kind,
+    /// The directory holding the per-year folders, if not the default.
+    pub data_dir: Option<PathBuf>,
+    /// Parse the CSVs afresh rather than reading the binary copy.
+    pub no_cache: bool,
+    pub corrections: Option<PathBuf>,
+    pub redistribution: Option<PathBuf>,
+    /// Colour parties in tables and charts.
+    pub colour: bool,
+}
+
+impl Config {
+    /// Read the config file, if there is one.
+    pub fn load() -> Result<Config, Error> {
+        match path() {
+            Some(path) if path.is_file() => {
+                let content = std::fs::read_to_string(path)?;
+                toml::from_str(&content).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+            }
+            _ => Ok(Config::default()),
+        }
+    }
+
+    /// The arguments bundled under the given preset name.
+    pub fn preset(&self, name: &str) -> Option<Vec<String>> {
+        self.presets.get(name).cloned().or_else(|| {
+            BUILTIN_PRESETS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, args)| args.iter().map(|a| a.to_string()).collect())
+        })
+    }
+
+    /// The names of all known presets, user-defined and built-in.
+    pub fn preset_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .presets
+            .keys()
+            .map(|k| k.as_str())
+            .chain(BUILTIN_PRESETS.iter().map(|(n, _)| *n))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+/// The location of the config file, respecting `XDG_CONFIG_HOME`.
+pub fn path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .map(|dir| dir.join("election").join("config.toml"))
+}
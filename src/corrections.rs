@@ -0,0 +1,80 @@
+//! Vote counts that replace the published ones, as when a judicial recount
+//! changed a riding's result after the poll-by-poll files came out. They're
+//! read from a CSV whose rows give the `year`, either the `district` number or
+//! the `riding` name, the `party`, and the candidate's corrected `votes`. A
+//! `last_name` column tells apart several independents in one riding.
+
+use crate::{fold, Party, Poll};
+use serde::Deserialize;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct Row {
+    year: usize,
+    district: Option<u32>,
+    riding: Option<String>,
+    party: Party,
+    last_name: Option<String>,
+    votes: usize,
+}
+
+impl Row {
+    fn matches(&self, poll: &Poll) -> bool {
+        let riding = match (self.district, &self.riding) {
+            (Some(d), _) => d == poll.district,
+            (None, Some(name)) => fold(name) == fold(&poll.riding),
+            (None, None) => false,
+        };
+        let name = self
+            .last_name
+            .as_ref()
+            .is_none_or(|n| fold(n) == fold(&poll.last_name));
+
+        riding && name && self.party == poll.party
+    }
+}
+
+/// Every correction in the corrections file.
+pub struct Corrections {
+    rows: Vec<Row>,
+}
+
+impl Corrections {
+    pub fn load(path: &Path) -> Result<Corrections, Error> {
+        let rows = csv::Reader::from_path(path)?
+            .deserialize()
+            .collect::<Result<_, _>>()?;
+
+        Ok(Corrections { rows })
+    }
+
+    /// Correct the totals of the given year's candidates, as fused. A
+    /// correction that matches no candidate is surely a mistake, and is
+    /// reported as such.
+    pub fn apply(&self, year: usize, polls: &mut [Poll]) -> Result<(), Error> {
+        for row in self.rows.iter().filter(|r| r.year == year) {
+            let mut matched = false;
+
+            for poll in polls.iter_mut().filter(|p| row.matches(p)) {
+                poll.correct(row.votes);
+                matched = true;
+            }
+
+            if !matched {
+                let riding = row
+                    .riding
+                    .clone()
+                    .or_else(|| row.district.map(|d| d.to_string()))
+                    .unwrap_or_default();
+                let msg = format!(
+                    "The {} correction for {:?} in {} matches no candidate",
+                    year, row.party, riding
+                );
+                return Err(Error::new(ErrorKind::InvalidData, msg));
+            }
+        }
+
+        Ok(())
+    }
+}
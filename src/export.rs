@@ -0,0 +1,459 @@
+//! `election export TARGET [--year YEAR] DEST`: an election's results in a
+//! form that other tools read directly.
+//!
+//! `sqlite` writes a database to query with SQL or browse with the tools that
+//! already read it. Its tables are normalised:
+//!
+//! - `parties`: every party that ran, and the colour it's drawn in.
+//! - `ridings`: each riding's district number, name, province, electors and
+//!   rejected ballots.
+//! - `candidates`: who ran where, and for which party. Independents are kept
+//!   apart, even when several ran in one riding.
+//! - `results`: each candidate's votes, in total and by kind of ballot.
+//!
+//! `parquet` writes a directory of two flat datasets for dataframe libraries
+//! like pandas, Polars and Spark: `polls.parquet`, with a row per candidate
+//! per poll as Elections Canada gives them, and `ridings.parquet`, with a row
+//! per candidate per riding. Both come from the same polls, so `--stdin`,
+//! `--classes` and the like shape them alike. Corrections only replace
+//! candidates' totals, though, so they reach `ridings.parquet` alone.
+//!
+//! `geojson` joins the results onto the district boundaries given with
+//! `--boundaries`, for choropleth maps. See [`crate::geo`].
+//!
+//! `ridings` writes a file per riding into a directory per province, like
+//! `ontario/35001.json`, for static sites that build a page from each. JSON
+//! files hold the riding's whole profile, as `election riding` gives it, and
+//! CSV files (with `--file-format csv`) a row per candidate.
+
+use crate::config::Settings;
+use crate::format::{label, Format};
+use crate::geo;
+use crate::privacy::SmallPolls;
+use crate::{corrected, fuse, profile_of, ridings, Candidate, Party, Poll, Riding};
+use clap::{ArgEnum, Clap};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::record::RecordWriter;
+use parquet_derive::ParquetRecordWriter;
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+
+#[derive(Clap, Debug)]
+pub struct ExportArgs {
+    /// What to write.
+    #[clap(arg_enum)]
+    target: Target,
+
+    /// A GeoJSON file of district boundaries, for geojson.
+    #[clap(long, value_name = "FILE", required_if_eq("target", "geojson"))]
+    boundaries: Option<PathBuf>,
+
+    /// The format of each riding's file, for ridings.
+    #[clap(long, arg_enum, default_value = "json", value_name = "FORMAT")]
+    file_format: RidingFormat,
+
+    /// Where to write it: a directory for parquet and ridings, and a file
+    /// otherwise.
+    #[clap(value_name = "DEST")]
+    dest: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum Target {
+    Sqlite,
+    Parquet,
+    Geojson,
+    Ridings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+enum RidingFormat {
+    Json,
+    Csv,
+}
+
+const SCHEMA: &str = "
+CREATE TABLE parties (
+    id     INTEGER PRIMARY KEY,
+    name   TEXT NOT NULL UNIQUE,
+    colour TEXT NOT NULL
+);
+CREATE TABLE ridings (
+    district INTEGER PRIMARY KEY,
+    name     TEXT NOT NULL,
+    province TEXT,
+    electors INTEGER NOT NULL,
+    rejected INTEGER NOT NULL
+);
+CREATE TABLE candidates (
+    id          INTEGER PRIMARY KEY,
+    district    INTEGER NOT NULL REFERENCES ridings (district),
+    party       INTEGER NOT NULL REFERENCES parties (id),
+    name        TEXT NOT NULL,
+    last_name   TEXT NOT NULL,
+    first_name  TEXT NOT NULL,
+    middle_name TEXT NOT NULL,
+    incumbent   INTEGER NOT NULL,
+    elected     INTEGER NOT NULL
+);
+CREATE TABLE results (
+    candidate INTEGER PRIMARY KEY REFERENCES candidates (id),
+    votes     INTEGER NOT NULL,
+    ordinary  INTEGER NOT NULL,
+    advance   INTEGER NOT NULL,
+    mobile    INTEGER NOT NULL,
+    special   INTEGER NOT NULL
+);
+";
+
+/// What was exported.
+#[derive(Serialize, JsonSchema)]
+pub struct Exported {
+    year: usize,
+    files: Vec<PathBuf>,
+    ridings: usize,
+    candidates: usize,
+}
+
+/// A candidate's result at one poll.
+#[derive(ParquetRecordWriter)]
+struct PollRow {
+    year: u32,
+    district: u32,
+    riding: String,
+    poll: String,
+    station: String,
+    void: bool,
+    not_held: bool,
+    party: String,
+    name: String,
+    last_name: String,
+    first_name: String,
+    middle_name: String,
+    votes: u64,
+    incumbent: bool,
+    elected: bool,
+    electors: u64,
+    rejected: u64,
+}
+
+/// A candidate's result across a riding.
+#[derive(ParquetRecordWriter)]
+struct RidingRow {
+    year: u32,
+    district: u32,
+    riding: String,
+    province: Option<String>,
+    electors: u64,
+    rejected: u64,
+    party: String,
+    name: String,
+    last_name: String,
+    first_name: String,
+    middle_name: String,
+    votes: u64,
+    ordinary: u64,
+    advance: u64,
+    mobile: u64,
+    special: u64,
+    incumbent: bool,
+    elected: bool,
+}
+
+/// Export the year's polls, as yet unfused, as `args` asks.
+pub fn export(
+    settings: &Settings,
+    args: &ExportArgs,
+    year: usize,
+    polls: Vec<Poll>,
+    small_polls: Option<SmallPolls>,
+) -> Result<Exported, Error> {
+    let ridings = ridings(corrected(settings, year, fuse(polls.clone()))?);
+    let dest = args.dest.clone();
+
+    match args.target {
+        Target::Sqlite => sqlite(year, &ridings, dest),
+        Target::Parquet => {
+            let polls = match small_polls {
+                Some(small) => small.apply(polls),
+                None => polls,
+            };
+            parquet(year, polls, &ridings, &dest)
+        }
+        Target::Geojson => {
+            let boundaries = args.boundaries.clone().unwrap_or_default();
+            geojson(year, &ridings, &boundaries, dest)
+        }
+        Target::Ridings => by_riding(year, &ridings, args.file_format, &dest),
+    }
+}
+
+/// Where to write `dest` until it's complete.
+fn part(dest: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.part", dest.display()))
+}
+
+/// Write a fresh database, moving it into place only once it's complete.
+fn sqlite(year: usize, ridings: &[Riding], file: PathBuf) -> Result<Exported, Error> {
+    let part = part(&file);
+    if part.exists() {
+        std::fs::remove_file(&part)?;
+    }
+
+    let mut conn = Connection::open(&part).map_err(Error::other)?;
+    let candidates = fill(&mut conn, ridings).map_err(Error::other)?;
+    conn.close().map_err(|(_, e)| Error::other(e))?;
+    std::fs::rename(&part, &file)?;
+
+    Ok(Exported {
+        year,
+        files: vec![file],
+        ridings: ridings.len(),
+        candidates,
+    })
+}
+
+/// Write the boundaries with the ridings' results joined onto them.
+fn geojson(
+    year: usize,
+    ridings: &[Riding],
+    boundaries: &Path,
+    file: PathBuf,
+) -> Result<Exported, Error> {
+    let (joined, matched) = geo::join(boundaries, ridings)?;
+
+    if matched == 0 && ridings.is_empty().not() {
+        let msg = format!(
+            "No district numbers in {} match {}'s ridings",
+            boundaries.display(),
+            year
+        );
+        return Err(Error::new(ErrorKind::InvalidData, msg));
+    }
+
+    let part = part(&file);
+    std::fs::write(&part, serde_json::to_string(&joined)?)?;
+    std::fs::rename(&part, &file)?;
+
+    Ok(Exported {
+        year,
+        files: vec![file],
+        ridings: matched,
+        candidates: ridings.iter().map(|r| r.individuals().count()).sum(),
+    })
+}
+
+/// Write each riding's file under `dir`, in a directory named after its
+/// province.
+fn by_riding(
+    year: usize,
+    ridings: &[Riding],
+    format: RidingFormat,
+    dir: &Path,
+) -> Result<Exported, Error> {
+    let (as_format, extension) = match format {
+        RidingFormat::Json => (Format::Json, "json"),
+        RidingFormat::Csv => (Format::Csv, "csv"),
+    };
+    let mut files = Vec::with_capacity(ridings.len());
+
+    for riding in ridings.iter() {
+        let province = riding
+            .province()
+            .map(|p| label(&p).to_lowercase().replace(' ', "-"))
+            .unwrap_or_else(|| "unknown".to_string());
+        let folder = dir.join(province);
+        std::fs::create_dir_all(&folder)?;
+
+        let profile = profile_of(riding, year);
+        let value = if format == RidingFormat::Csv {
+            let mut rows = serde_json::to_value(&profile.candidates)?;
+            for row in rows.as_array_mut().into_iter().flatten() {
+                if let Some(row) = row.as_object_mut() {
+                    row.insert("district".into(), riding.district.into());
+                    row.insert("riding".into(), riding.name.clone().into());
+                }
+            }
+            rows
+        } else {
+            serde_json::to_value(&profile)?
+        };
+
+        let file = folder.join(format!("{}.{}", riding.district, extension));
+        std::fs::write(&file, format!("{}\n", as_format.render(value, false)?))?;
+        files.push(file);
+    }
+
+    Ok(Exported {
+        year,
+        files,
+        ridings: ridings.len(),
+        candidates: ridings.iter().map(|r| r.individuals().count()).sum(),
+    })
+}
+
+/// Write both datasets into `dir`, creating it if need be.
+fn parquet(
+    year: usize,
+    polls: Vec<Poll>,
+    ridings: &[Riding],
+    dir: &Path,
+) -> Result<Exported, Error> {
+    std::fs::create_dir_all(dir)?;
+
+    let polls: Vec<PollRow> = polls.into_iter().map(|p| PollRow::new(year, p)).collect();
+    let candidates: Vec<RidingRow> = ridings
+        .iter()
+        .flat_map(|r| {
+            r.individuals()
+                .map(move |(p, c)| RidingRow::new(year, r, p, c))
+        })
+        .collect();
+
+    let files = vec![dir.join("polls.parquet"), dir.join("ridings.parquet")];
+    write_parquet(&polls, &files[0])?;
+    write_parquet(&candidates, &files[1])?;
+
+    Ok(Exported {
+        year,
+        files,
+        ridings: ridings.len(),
+        candidates: candidates.len(),
+    })
+}
+
+/// Write rows as a single row group, moving the file into place once it's
+/// complete.
+fn write_parquet<'a, T>(rows: &'a [T], file: &Path) -> Result<(), Error>
+where
+    &'a [T]: RecordWriter<T>,
+{
+    let part = part(file);
+    let schema = rows.schema().map_err(Error::other)?;
+    let mut writer =
+        SerializedFileWriter::new(std::fs::File::create(&part)?, schema, Default::default())
+            .map_err(Error::other)?;
+    let mut group = writer.next_row_group().map_err(Error::other)?;
+    rows.write_to_row_group(&mut group).map_err(Error::other)?;
+    group.close().map_err(Error::other)?;
+    writer.close().map_err(Error::other)?;
+
+    std::fs::rename(&part, file)
+}
+
+impl PollRow {
+    fn new(year: usize, p: Poll) -> PollRow {
+        PollRow {
+            year: year as u32,
+            name: p.name(),
+            district: p.district,
+            riding: p.riding,
+            poll: p.number,
+            station: p.station,
+            void: p.void,
+            not_held: p.not_held,
+            party: label(&p.party),
+            last_name: p.last_name,
+            first_name: p.first_name,
+            middle_name: p.middle_name,
+            votes: p.votes as u64,
+            incumbent: p.incumbent,
+            elected: p.elected,
+            electors: p.electors as u64,
+            rejected: p.rejected as u64,
+        }
+    }
+}
+
+impl RidingRow {
+    fn new(year: usize, r: &Riding, p: &Party, c: &Candidate) -> RidingRow {
+        RidingRow {
+            year: year as u32,
+            district: r.district,
+            riding: r.name.clone(),
+            province: r.province().map(|p| label(&p)),
+            electors: r.electors as u64,
+            rejected: r.rejected as u64,
+            party: label(p),
+            name: c.name(),
+            last_name: c.last_name.clone(),
+            first_name: c.first_name.clone(),
+            middle_name: c.middle_name.clone(),
+            votes: c.votes as u64,
+            ordinary: c.ballots.ordinary as u64,
+            advance: c.ballots.advance as u64,
+            mobile: c.ballots.mobile as u64,
+            special: c.ballots.special as u64,
+            incumbent: c.incumbent,
+            elected: c.elected,
+        }
+    }
+}
+
+/// Create the tables and fill them, giving the number of candidates.
+fn fill(conn: &mut Connection, ridings: &[Riding]) -> rusqlite::Result<usize> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+
+    let mut parties: BTreeMap<Party, usize> = BTreeMap::new();
+    let mut count = 0;
+
+    for riding in ridings.iter() {
+        tx.execute(
+            "INSERT INTO ridings VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                riding.district,
+                riding.name,
+                riding.province().map(|p| label(&p)),
+                riding.electors,
+                riding.rejected
+            ],
+        )?;
+
+        for (party, c) in riding.individuals() {
+            let next = parties.len() + 1;
+            let id = *parties.entry(party.clone()).or_insert_with(|| next);
+            if id == next {
+                tx.execute(
+                    "INSERT INTO parties VALUES (?1, ?2, ?3)",
+                    params![id, label(party), party.colour()],
+                )?;
+            }
+
+            count += 1;
+            tx.execute(
+                "INSERT INTO candidates VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    count,
+                    riding.district,
+                    id,
+                    c.name(),
+                    c.last_name,
+                    c.first_name,
+                    c.middle_name,
+                    c.incumbent,
+                    c.elected
+                ],
+            )?;
+            tx.execute(
+                "INSERT INTO results VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    count,
+                    c.votes,
+                    c.ballots.ordinary,
+                    c.ballots.advance,
+                    c.ballots.mobile,
+                    c.ballots.special
+                ],
+            )?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(count)
+}
@@ -0,0 +1,143 @@
+//! `election fetch YEAR [URL]`: download a year's poll-by-poll results from
+//! Elections Canada and unpack them into its data directory.
+//!
+//! Downloads go to a `.part` file first, and an interrupted download picks up
+//! where it left off the next time.
+
+use crate::config::Settings;
+use crate::data_dir;
+use clap::Clap;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Where Elections Canada keeps each year's Format 2 archive. Older years
+/// moved around too often to list, so their URL has to be given.
+fn official_url(year: usize) -> Option<&'static str> {
+    match year {
+        2015 => Some("https://elections.ca/res/rep/off/ovr2015app/41/data_donnees/pollresults_resultatsbureauCanada.zip"),
+        2019 => Some("https://elections.ca/res/rep/off/ovr2019app/51/data_donnees/pollresults_resultatsbureauCanada.zip"),
+        2021 => Some("https://elections.ca/res/rep/off/ovr2021app/53/data_donnees/pollresults_resultatsbureauCanada.zip"),
+        _ => None,
+    }
+}
+
+#[derive(Clap, Debug)]
+pub struct FetchArgs {
+    /// The election year to download.
+    #[clap(name = "YEAR")]
+    year: usize,
+
+    /// Where to download it from, for years before 2015.
+    url: Option<String>,
+}
+
+/// What was fetched.
+#[derive(Serialize, JsonSchema)]
+pub struct Fetched {
+    year: usize,
+    url: String,
+    bytes: u64,
+    files: usize,
+}
+
+/// Download and unpack the archive for the year given in `args`.
+pub fn fetch(settings: &Settings, args: &FetchArgs) -> Result<Fetched, Error> {
+    let year = args.year;
+    let url = args
+        .url
+        .as_deref()
+        .or_else(|| official_url(year))
+        .ok_or_else(|| {
+            let msg = format!("No known download for {}; please give its URL", year);
+            Error::new(ErrorKind::InvalidInput, msg)
+        })?;
+
+    let dir = data_dir(settings, year);
+    std::fs::create_dir_all(&dir)?;
+
+    let archive = dir.with_extension("zip");
+    let bytes = download(url, &archive)?;
+    let files = unpack(&archive, &dir)?;
+    std::fs::remove_file(&archive)?;
+
+    Ok(Fetched {
+        year,
+        url: url.to_string(),
+        bytes,
+        files,
+    })
+}
+
+/// Download to `dest`, resuming a previous partial download if there is one,
+/// and check that the whole file arrived.
+fn download(url: &str, dest: &Path) -> Result<u64, Error> {
+    let part = PathBuf::from(format!("{}.part", dest.display()));
+    let have = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+
+    let mut request = ureq::get(url);
+    if have > 0 {
+        request = request.set("Range", &format!("bytes={}-", have));
+    }
+    let response = request.call().map_err(Error::other)?;
+
+    // A server that ignores the range sends the whole file again.
+    let resumed = response.status() == 206;
+    let expected = if resumed {
+        response
+            .header("Content-Range")
+            .and_then(|r| r.rsplit('/').next())
+            .and_then(|total| total.parse::<u64>().ok())
+    } else {
+        response
+            .header("Content-Length")
+            .and_then(|l| l.parse::<u64>().ok())
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part)?;
+    std::io::copy(&mut response.into_reader(), &mut file)?;
+    file.flush()?;
+
+    let size = file.metadata()?.len();
+
+    match expected {
+        Some(e) if e != size => {
+            let msg = format!("Expected {} bytes but got {}; run again to resume", e, size);
+            Err(Error::new(ErrorKind::UnexpectedEof, msg))
+        }
+        _ => {
+            std::fs::rename(&part, dest)?;
+            Ok(size)
+        }
+    }
+}
+
+/// Extract the CSVs of an archive into a directory, returning how many there
+/// were.
+fn unpack(archive: &Path, dir: &Path) -> Result<usize, Error> {
+    let mut zip = zip::ZipArchive::new(std::fs::File::open(archive)?)?;
+    let mut count = 0;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+
+        // Only plain file names are trusted, never paths.
+        let name = match entry.enclosed_name().and_then(|p| p.file_name()) {
+            Some(n) if n.to_string_lossy().ends_with(".csv") => n.to_owned(),
+            _ => continue,
+        };
+
+        let mut file = std::fs::File::create(dir.join(name))?;
+        std::io::copy(&mut entry, &mut file)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,537 @@
+//! Rendering results as something other than JSON.
+//!
+//! Delimited formats and tables need rows and columns. A result that's a list
+//! of records gives a row per record, with nested fields flattened into dotted
+//! column names like `government.seats`. Any other result is first reshaped
+//! into long format, as with `--tidy`.
+
+use crate::{lang, tidy, Party};
+use clap::ArgEnum;
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+use std::io::{Error, IsTerminal};
+use std::path::Path;
+
+/// The formats that results can be rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Format {
+    Json,
+    Csv,
+    Tsv,
+    /// Aligned columns for reading in a terminal.
+    Table,
+    /// GitHub-flavoured Markdown tables.
+    Markdown,
+    Yaml,
+    /// LaTeX tables in the style of the booktabs package.
+    Latex,
+}
+
+impl Format {
+    /// JSON, unless results are headed for a terminal, where a table is easier
+    /// to read.
+    pub fn detect(sink: Option<&str>) -> Format {
+        let stdout = matches!(sink, None | Some("stdout") | Some("-"));
+
+        if stdout && std::io::stdout().is_terminal() {
+            Format::Table
+        } else {
+            Format::Json
+        }
+    }
+
+    /// The format that a file's extension names, if it names one.
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "md" | "markdown" => Some(Format::Markdown),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "tex" => Some(Format::Latex),
+            _ => None,
+        }
+    }
+
+    /// Is this format meant to be read by people, rather than programs?
+    pub fn is_readable(&self) -> bool {
+        matches!(self, Format::Table | Format::Markdown | Format::Latex)
+    }
+
+    /// The media type of this format, for sinks that announce one.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Csv => "text/csv",
+            Format::Tsv => "text/tab-separated-values",
+            Format::Table => "text/plain",
+            Format::Markdown => "text/markdown",
+            Format::Yaml => "application/yaml",
+            Format::Latex => "application/x-latex",
+        }
+    }
+
+    /// Render a result, already converted to JSON, in this format. Only tables
+    /// are ever coloured.
+    pub fn render(&self, value: Value, colour: bool) -> Result<String, Error> {
+        match self {
+            Format::Json => Ok(serde_json::to_string(&value)?),
+            Format::Csv => delimited(value, b','),
+            Format::Tsv => delimited(value, b'\t'),
+            Format::Table => Ok(table(value, colour)),
+            Format::Markdown => Ok(markdown(value)),
+            Format::Yaml => yaml(value),
+            Format::Latex => Ok(latex(value)),
+        }
+    }
+}
+
+/// A header row followed by a row per record.
+fn delimited(value: Value, delimiter: u8) -> Result<String, Error> {
+    let rows = records(value);
+    let columns = columns(&rows);
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+
+    if !columns.is_empty() {
+        writer.write_record(&columns)?;
+    }
+    for row in rows.iter() {
+        writer.write_record(columns.iter().map(|c| cell(row.get(c))))?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    let body = String::from_utf8(bytes).map_err(Error::other)?;
+
+    // Sinks add their own final newline.
+    Ok(body.trim_end_matches('\n').to_string())
+}
+
+/// A result laid out for reading, with each cell already formatted.
+struct Grid {
+    columns: Vec<String>,
+    cells: Vec<Vec<String>>,
+    /// Which columns hold only numbers, to be lined up on the right.
+    numeric: Vec<bool>,
+    widths: Vec<usize>,
+}
+
+impl Grid {
+    fn new(value: Value) -> Grid {
+        let rows = records(value);
+        let columns = columns(&rows);
+
+        let cells: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|c| pretty(field(c, row), row.get(c)))
+                    .collect()
+            })
+            .collect();
+        let numeric = columns
+            .iter()
+            .map(|c| {
+                rows.iter()
+                    .all(|row| row.get(c).is_none_or(|v| v.is_number() || v.is_null()))
+            })
+            .collect();
+        let widths = columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                cells
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain(std::iter::once(c.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        Grid {
+            columns,
+            cells,
+            numeric,
+            widths,
+        }
+    }
+
+    /// Pad each cell of a row to the width of its column.
+    fn padded<'a>(&'a self, row: &'a [String]) -> impl Iterator<Item = String> + 'a {
+        row.iter().enumerate().map(move |(i, cell)| {
+            if self.numeric[i] {
+                format!("{:>1$}", cell, self.widths[i])
+            } else {
+                format!("{:<1$}", cell, self.widths[i])
+            }
+        })
+    }
+}
+
+/// The result as it is, nesting and all, for tools that read YAML data files.
+fn yaml(value: Value) -> Result<String, Error> {
+    let body = serde_yaml::to_string(&value).map_err(Error::other)?;
+
+    // Sinks add their own final newline.
+    Ok(body.trim_end_matches('\n').to_string())
+}
+
+/// Headers over aligned columns, with numbers made easier on the eye and
+/// lined up on the right. Parties are shown in their colours if asked.
+fn table(value: Value, colour: bool) -> String {
+    let grid = Grid::new(value);
+    let line = |row: &[String]| grid.padded(row).join("  ").trim_end().to_string();
+    let rule: Vec<String> = grid.widths.iter().map(|w| "-".repeat(*w)).collect();
+    let colours = if colour {
+        party_colours()
+    } else {
+        HashMap::new()
+    };
+    let painted = |row: &[String]| {
+        let cells: Vec<String> = grid
+            .padded(row)
+            .zip(row.iter())
+            .map(|(padded, cell)| match colours.get(cell) {
+                Some(rgb) => padded.replacen(cell.as_str(), &paint(cell, rgb), 1),
+                None => padded,
+            })
+            .collect();
+        cells.join("  ").trim_end().to_string()
+    };
+
+    std::iter::once(line(&grid.columns))
+        .chain(std::iter::once(rule.join("  ")))
+        .chain(grid.cells.iter().map(|row| painted(row)))
+        .join("\n")
+}
+
+/// Can tables be coloured? Only on a terminal, and not if `NO_COLOR` says
+/// otherwise.
+pub fn coloured() -> bool {
+    std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal()
+}
+
+/// Each party's name as it appears in results, and its colour.
+fn party_colours() -> HashMap<String, (u8, u8, u8)> {
+    Party::all()
+        .flat_map(|p| [(label(&p), p.rgb()), (p.french(), p.rgb())])
+        .collect()
+}
+
+/// Text in the given colour, for terminals that understand 24-bit colour.
+pub fn paint(text: &str, (r, g, b): &(u8, u8, u8)) -> String {
+    format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, text)
+}
+
+/// A GitHub-flavoured Markdown table, formatted as for a terminal.
+fn markdown(value: Value) -> String {
+    let grid = Grid::new(value);
+    let escape =
+        |row: &[String]| -> Vec<String> { row.iter().map(|c| c.replace('|', "\\|")).collect() };
+    let line = |row: &[String]| format!("| {} |", grid.padded(&escape(row)).join(" | "));
+    let rule: Vec<String> = grid
+        .widths
+        .iter()
+        .zip(grid.numeric.iter())
+        .map(|(w, numeric)| {
+            if *numeric {
+                format!("{}:", "-".repeat(w.saturating_sub(1).max(2)))
+            } else {
+                "-".repeat(*w.max(&3))
+            }
+        })
+        .collect();
+
+    std::iter::once(line(&grid.columns))
+        .chain(std::iter::once(format!("| {} |", rule.join(" | "))))
+        .chain(grid.cells.iter().map(|row| line(row)))
+        .join("\n")
+}
+
+/// A `tabular` with booktabs rules, to `\input` into a paper. Numeric
+/// columns are right-aligned. Needs `\usepackage{booktabs}`.
+fn latex(value: Value) -> String {
+    let grid = Grid::new(value);
+    let spec: String = grid
+        .numeric
+        .iter()
+        .map(|n| if *n { 'r' } else { 'l' })
+        .collect();
+    let line = |row: &[String]| {
+        let cells: Vec<String> = row.iter().map(|c| tex(c)).collect();
+        format!("{} \\\\", cells.join(" & "))
+    };
+
+    std::iter::once(format!("\\begin{{tabular}}{{{}}}", spec))
+        .chain(std::iter::once("\\toprule".to_string()))
+        .chain(std::iter::once(line(&grid.columns)))
+        .chain(std::iter::once("\\midrule".to_string()))
+        .chain(grid.cells.iter().map(|row| line(row)))
+        .chain(std::iter::once("\\bottomrule".to_string()))
+        .chain(std::iter::once("\\end{tabular}".to_string()))
+        .join("\n")
+}
+
+/// Text with LaTeX's special characters escaped.
+fn tex(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".to_string(),
+            '~' => "\\textasciitilde{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{}", c),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// An HTML table, formatted as for a terminal. Numeric columns are marked as
+/// such, for styling and sorting.
+pub fn html(value: Value) -> String {
+    let grid = Grid::new(value);
+    let class = |i: usize| {
+        if grid.numeric[i] {
+            " class=\"num\""
+        } else {
+            ""
+        }
+    };
+
+    let head: String = grid
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("<th{}>{}</th>", class(i), escape(c)))
+        .collect();
+    let body: String = grid
+        .cells
+        .iter()
+        .map(|row| {
+            let cells: String = row
+                .iter()
+                .enumerate()
+                .map(|(i, c)| format!("<td{}>{}</td>", class(i), escape(c)))
+                .collect();
+            format!("<tr>{}</tr>\n", cells)
+        })
+        .collect();
+
+    format!(
+        "<table>\n<thead><tr>{}</tr></thead>\n<tbody>\n{}</tbody>\n</table>",
+        head, body
+    )
+}
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A party, province or the like by the name it goes by in results.
+pub fn label<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+/// Records needn't all have the same fields, so the columns are every field
+/// seen, in the order first seen.
+fn columns(rows: &[Map<String, Value>]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+
+    for row in rows.iter() {
+        for key in row.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    columns
+}
+
+/// The result as a list of records, reshaped into long format unless it
+/// already is one.
+pub fn tabular(value: Value) -> Value {
+    match value {
+        Value::Array(items) if items.iter().all(|i| i.is_object()) => Value::Array(items),
+        other => tidy::reshape(other),
+    }
+}
+
+/// The result as a list of flat records.
+fn records(value: Value) -> Vec<Map<String, Value>> {
+    match tabular(value) {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| {
+                let mut row = Map::new();
+                flatten("", item, &mut row);
+                row
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Lift the fields of nested objects into their parent, under dotted names.
+fn flatten(prefix: &str, value: Value, row: &mut Map<String, Value>) {
+    match value {
+        Value::Object(fields) => {
+            for (k, v) in fields {
+                let key = if prefix.is_empty() {
+                    k
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten(&key, v, row);
+            }
+        }
+        other => {
+            row.insert(prefix.to_string(), other);
+        }
+    }
+}
+
+/// A value as it appears in a cell. Lists are left as JSON.
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Fields whose values are shares of something, like a share of the vote or
+/// of the eligible voters, and so are shown as percentages. So is anything
+/// ending in `_ratio` or `_margin`.
+const SHARES: &[&str] = &[
+    "advance",
+    "after",
+    "average",
+    "before",
+    "closest",
+    "coverage",
+    "difference",
+    "divergence",
+    "election_day",
+    "from",
+    "hhi",
+    "in_person",
+    "index",
+    "largest_swing",
+    "margin",
+    "mean",
+    "median",
+    "national",
+    "rate",
+    "ratio",
+    "share",
+    "shares",
+    "special",
+    "swing",
+    "threshold",
+    "to",
+    "turnout",
+];
+
+/// The field that a cell's value belongs to. That's its column, except in
+/// long format, where it's the row's `metric`.
+fn field<'a>(column: &'a str, row: &'a Map<String, Value>) -> &'a str {
+    let metric = row
+        .get("metric")
+        .or_else(|| row.get(lang::french("metric")?));
+
+    match metric {
+        Some(Value::String(m)) if column == "value" || lang::french("value") == Some(column) => m,
+        _ => column,
+    }
+}
+
+/// A value as a person would rather read it: counts with thousands
+/// separators, and shares as percentages.
+fn pretty(field: &str, value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Number(n)) if is_id(field) => n.to_string(),
+        Some(Value::Number(n)) => number(n, is_share(field)),
+        other => cell(other),
+    }
+}
+
+/// Is this dotted field one of [`SHARES`], in any language?
+fn is_share(field: &str) -> bool {
+    let name = leaf(field);
+
+    name.ends_with("_ratio")
+        || name.ends_with("_margin")
+        || SHARES
+            .iter()
+            .any(|s| *s == name || lang::french(s) == Some(name))
+}
+
+/// The innermost field named in a dotted path, like `district` in
+/// `before.district`. Map keys and list indices aren't fields, so
+/// `shares.Liberal` and `relative.0` give `shares` and `relative`.
+fn leaf(field: &str) -> &str {
+    field
+        .rsplit('.')
+        .find(|name| name.parse::<usize>().is_err() && !is_party(name))
+        .unwrap_or(field)
+}
+
+/// Is this a party's name, in any language?
+fn is_party(name: &str) -> bool {
+    Party::all().any(|p| label(&p) == name || p.french() == name)
+}
+
+/// Is this dotted field one of [`tidy::NUMERIC_IDS`], in any language?
+fn is_id(field: &str) -> bool {
+    let name = leaf(field);
+
+    tidy::NUMERIC_IDS
+        .iter()
+        .any(|id| *id == name || lang::french(id) == Some(name))
+}
+
+fn number(n: &Number, share: bool) -> String {
+    if let Some(i) = n.as_i64() {
+        return thousands(i);
+    }
+
+    match n.as_f64() {
+        Some(f) if share => format!("{:.2}%", f * 100.0),
+        Some(f) => {
+            let rounded = format!("{:.2}", f);
+            let (whole, fraction) = rounded.split_once('.').unwrap_or((&rounded, "00"));
+            format!("{}.{}", thousands(whole.parse().unwrap_or(0)), fraction)
+        }
+        None => n.to_string(),
+    }
+}
+
+/// `1234567` as `1,234,567`.
+fn thousands(n: i64) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let mut out = String::new();
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+
+    if n < 0 {
+        format!("-{}", out)
+    } else {
+        out
+    }
+}
@@ -0,0 +1,79 @@
+//! Results joined onto electoral district boundaries, for choropleth maps.
+//!
+//! Boundary files are GeoJSON feature collections, like those Elections
+//! Canada and Statistics Canada publish, whose features name their district
+//! number under one of [`DISTRICT_KEYS`]. Each feature keeps its geometry and
+//! gains the riding's winner, margin, and every party's share of the vote.
+//! Features for districts without results are left as they are.
+
+use crate::format::label;
+use crate::Riding;
+use itertools::Itertools;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// The properties that boundary files have been seen to give district
+/// numbers under.
+const DISTRICT_KEYS: &[&str] = &["FED_NUM", "FEDNUM", "FEDUID", "ED_NUM", "district"];
+
+/// Read a boundary file and join the ridings' results onto it, giving the
+/// joined collection and how many of its features were matched.
+pub fn join(boundaries: &Path, ridings: &[Riding]) -> Result<(Value, usize), Error> {
+    let mut collection: Value = serde_json::from_reader(std::fs::File::open(boundaries)?)?;
+    let ridings: HashMap<u32, &Riding> = ridings.iter().map(|r| (r.district, r)).collect();
+    let mut matched = 0;
+
+    let features = collection
+        .get_mut("features")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| {
+            let msg = format!("Not a GeoJSON FeatureCollection: {}", boundaries.display());
+            Error::new(ErrorKind::InvalidData, msg)
+        })?;
+
+    for feature in features.iter_mut() {
+        let props = match feature.get_mut("properties").and_then(Value::as_object_mut) {
+            Some(props) => props,
+            None => continue,
+        };
+
+        if let Some(riding) = district(props).and_then(|d| ridings.get(&d)) {
+            properties(riding, props);
+            matched += 1;
+        }
+    }
+
+    Ok((collection, matched))
+}
+
+/// The district number that a feature's properties give, if any.
+fn district(props: &Map<String, Value>) -> Option<u32> {
+    DISTRICT_KEYS
+        .iter()
+        .filter_map(|k| props.get(*k))
+        .find_map(|v| match v {
+            Value::Number(n) => n.as_u64().map(|n| n as u32),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        })
+}
+
+/// Add a riding's results to a feature. Shares are flat properties named
+/// like `share_Liberal`, since map tools rarely look inside nested ones.
+fn properties(riding: &Riding, props: &mut Map<String, Value>) {
+    let winner = riding.winner();
+
+    props.insert("district".into(), riding.district.into());
+    props.insert("riding".into(), riding.name.clone().into());
+    props.insert("winner".into(), label(&winner).into());
+    props.insert("colour".into(), winner.colour().into());
+    props.insert("margin".into(), riding.victory_margin().into());
+
+    for party in riding.candidates.keys().sorted() {
+        if let Some(share) = riding.share(party) {
+            props.insert(format!("share_{}", label(party)), share.into());
+        }
+    }
+}
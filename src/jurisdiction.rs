@@ -0,0 +1,318 @@
+//! Whose elections are being analysed. Canada's own are the default, but the
+//! provinces hold general elections too, fought between parties of their own
+//! and published in formats of their own.
+//!
+//! Each jurisdiction says in which years it held elections, where their
+//! results live, which parties the labels in them stand for, and which columns
+//! of its CSVs to read. Elections Canada's formats are read by the loader
+//! itself. The provinces' are read here, a row at a time, into the same polls,
+//! so that every analysis runs on them unchanged.
+
+use crate::{Party, Poll};
+use clap::ArgEnum;
+use csv::StringRecord;
+use serde_json::Value;
+use std::io::{Error, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A government whose general elections can be analysed.
+pub trait Jurisdiction: Sync {
+    /// Its name, as given to `--jurisdiction`.
+    fn name(&self) -> &'static str;
+
+    /// The years of its general elections.
+    fn years(&self) -> &'static [usize];
+
+    /// Where the results of the given year live, within the data directory.
+    fn dir(&self, root: &Path, year: usize) -> PathBuf {
+        root.join(self.name()).join(year.to_string())
+    }
+
+    /// The party that a label in its results stands for, if it's one of its
+    /// own.
+    fn party(&self, label: &str) -> Option<Party>;
+
+    /// The columns of its results, unless they're Elections Canada's, which
+    /// the loader reads itself.
+    fn schema(&self) -> Option<&Schema>;
+
+    /// Read one row of its results as a poll. Candidates of parties it doesn't
+    /// list are counted as [`Party::OTH`].
+    fn poll(&self, headers: &StringRecord, row: &StringRecord) -> Result<Poll, Error> {
+        let schema = self.schema().ok_or_else(|| {
+            let msg = format!("{} results have no schema to read rows by", self.name());
+            Error::new(ErrorKind::InvalidInput, msg)
+        })?;
+        let field = |column| schema.field(headers, row, column);
+        let (first_name, last_name) = names(field(schema.candidate));
+
+        let poll = Poll {
+            district: number(schema.district, field(schema.district))?,
+            riding: field(schema.riding).to_string(),
+            riding_fr: String::new(),
+            number: field(schema.poll).to_string(),
+            station: String::new(),
+            void: false,
+            not_held: false,
+            party: self.party(field(schema.party)).unwrap_or(Party::OTH),
+            last_name,
+            first_name,
+            middle_name: String::new(),
+            votes: number(schema.votes, field(schema.votes))?,
+            incumbent: false,
+            elected: false,
+            electors: 0,
+            rejected: 0,
+            ballots: Default::default(),
+        };
+
+        Ok(poll.classify())
+    }
+}
+
+/// The jurisdictions there are.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Kind {
+    #[default]
+    Federal,
+    Ontario,
+    BritishColumbia,
+    Quebec,
+}
+
+impl Kind {
+    pub fn get(self) -> &'static dyn Jurisdiction {
+        match self {
+            Kind::Federal => &Federal,
+            Kind::Ontario => &ONTARIO,
+            Kind::BritishColumbia => &BRITISH_COLUMBIA,
+            Kind::Quebec => &QUEBEC,
+        }
+    }
+}
+
+/// Canada's own general elections, as published by Elections Canada.
+pub struct Federal;
+
+impl Jurisdiction for Federal {
+    fn name(&self) -> &'static str {
+        "federal"
+    }
+
+    fn years(&self) -> &'static [usize] {
+        &[2004, 2006, 2008, 2011, 2015, 2019, 2021]
+    }
+
+    /// Federal results sit directly within the data directory, as they did
+    /// before there were any others.
+    fn dir(&self, root: &Path, year: usize) -> PathBuf {
+        root.join(year.to_string())
+    }
+
+    fn party(&self, label: &str) -> Option<Party> {
+        serde_json::from_value(Value::String(label.to_string())).ok()
+    }
+
+    fn schema(&self) -> Option<&Schema> {
+        None
+    }
+}
+
+/// A province, whose results come as a CSV of its own design with a row per
+/// candidate per poll.
+pub struct Provincial {
+    name: &'static str,
+    years: &'static [usize],
+    schema: Schema,
+    /// Its parties, by every label its results give them.
+    parties: &'static [(&'static str, Party)],
+}
+
+impl Jurisdiction for Provincial {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn years(&self) -> &'static [usize] {
+        self.years
+    }
+
+    fn party(&self, label: &str) -> Option<Party> {
+        self.parties
+            .iter()
+            .find(|(l, _)| l.eq_ignore_ascii_case(label.trim()))
+            .map(|(_, party)| party.clone())
+    }
+
+    fn schema(&self) -> Option<&Schema> {
+        Some(&self.schema)
+    }
+}
+
+/// The headers of the columns that a poll is read from.
+pub struct Schema {
+    district: &'static str,
+    riding: &'static str,
+    poll: &'static str,
+    /// The candidate's full name, as either `First Last` or `Last, First`.
+    candidate: &'static str,
+    party: &'static str,
+    votes: &'static str,
+}
+
+impl Schema {
+    fn columns(&self) -> [&'static str; 6] {
+        [
+            self.district,
+            self.riding,
+            self.poll,
+            self.candidate,
+            self.party,
+            self.votes,
+        ]
+    }
+
+    /// Do these headers have every column?
+    fn recognizes(&self, headers: &StringRecord) -> bool {
+        self.columns()
+            .iter()
+            .all(|c| headers.iter().any(|h| h.trim() == *c))
+    }
+
+    /// The value of a row in the given column, or nothing if it's missing.
+    fn field<'r>(&self, headers: &StringRecord, row: &'r StringRecord, column: &str) -> &'r str {
+        headers
+            .iter()
+            .position(|h| h.trim() == column)
+            .and_then(|i| row.get(i))
+            .unwrap_or_default()
+            .trim()
+    }
+
+    /// The party label of a row.
+    pub fn label<'r>(&self, headers: &StringRecord, row: &'r StringRecord) -> &'r str {
+        self.field(headers, row, self.party)
+    }
+}
+
+pub const ONTARIO: Provincial = Provincial {
+    name: "ontario",
+    years: &[2014, 2018, 2022, 2025],
+    schema: Schema {
+        district: "Electoral District Number",
+        riding: "Electoral District Name",
+        poll: "Poll Number",
+        candidate: "Candidate",
+        party: "Political Interest Code",
+        votes: "Votes",
+    },
+    parties: &[
+        ("PC", Party::PCO),
+        ("PCP", Party::PCO),
+        ("Progressive Conservative Party of Ontario", Party::PCO),
+        ("NDP", Party::NDP),
+        ("Ontario New Democratic Party", Party::NDP),
+        ("LIB", Party::LIB),
+        ("Ontario Liberal Party", Party::LIB),
+        ("GPO", Party::GRN),
+        ("Green Party of Ontario", Party::GRN),
+        ("IND", Party::IND),
+        ("Independent", Party::IND),
+    ],
+};
+
+pub const BRITISH_COLUMBIA: Provincial = Provincial {
+    name: "british-columbia",
+    years: &[2017, 2020, 2024],
+    schema: Schema {
+        district: "ED Number",
+        riding: "ED Name",
+        poll: "Voting Area",
+        candidate: "Candidate's Ballot Name",
+        party: "Affiliation",
+        votes: "Votes Considered",
+    },
+    parties: &[
+        ("BC NDP", Party::NDP),
+        ("BC New Democratic Party", Party::NDP),
+        ("BC United", Party::BCU),
+        ("BC Liberal Party", Party::BCU),
+        ("Conservative Party of BC", Party::CON),
+        ("BC Conservative Party", Party::CON),
+        ("BC Green Party", Party::GRN),
+        ("Green Party of BC", Party::GRN),
+        ("BC Libertarian Party", Party::LTN),
+        ("Independent", Party::IND),
+    ],
+};
+
+pub const QUEBEC: Provincial = Provincial {
+    name: "quebec",
+    years: &[2014, 2018, 2022],
+    schema: Schema {
+        district: "Numéro de circonscription",
+        riding: "Circonscription",
+        poll: "Section de vote",
+        candidate: "Candidat",
+        party: "Parti politique",
+        votes: "Votes valides",
+    },
+    parties: &[
+        ("CAQ", Party::CAQ),
+        ("Coalition avenir Québec", Party::CAQ),
+        ("PLQ", Party::LIB),
+        ("Parti libéral du Québec", Party::LIB),
+        ("QS", Party::QS),
+        ("Québec solidaire", Party::QS),
+        ("PQ", Party::PQ),
+        ("Parti québécois", Party::PQ),
+        ("PCQ", Party::CON),
+        ("Parti conservateur du Québec", Party::CON),
+        ("PVQ", Party::GRN),
+        ("Parti vert du Québec", Party::GRN),
+        ("Indépendant", Party::IND),
+    ],
+};
+
+/// The province whose results have these headers, if any.
+pub fn provincial(headers: &StringRecord) -> Option<&'static dyn Jurisdiction> {
+    vec![Kind::Ontario, Kind::BritishColumbia, Kind::Quebec]
+        .into_iter()
+        .map(Kind::get)
+        .find(|j| j.schema().is_some_and(|s| s.recognizes(headers)))
+}
+
+/// Read a province's results as if they were Elections Canada's poll-by-poll
+/// data.
+pub fn read<R: Read>(
+    jurisdiction: &dyn Jurisdiction,
+    mut reader: csv::Reader<R>,
+) -> Result<Vec<Poll>, Error> {
+    let headers = reader.headers()?.clone();
+
+    reader
+        .records()
+        .filter(|r| r.as_ref().map_or(true, |r| *r != headers))
+        .map(|r| jurisdiction.poll(&headers, &r?))
+        .collect()
+}
+
+/// The number in a column of a row.
+fn number<T: FromStr>(column: &str, value: &str) -> Result<T, Error> {
+    value.parse().map_err(|_| {
+        let msg = format!("Not a number in {}: {}", column, value);
+        Error::new(ErrorKind::InvalidData, msg)
+    })
+}
+
+/// A candidate's first and last names, from either `First Last` or
+/// `Last, First`.
+fn names(candidate: &str) -> (String, String) {
+    let (first, last) = match candidate.split_once(',') {
+        Some((last, first)) => (first, last),
+        None => candidate.rsplit_once(' ').unwrap_or(("", candidate)),
+    };
+
+    (first.trim().to_string(), last.trim().to_string())
+}
@@ -0,0 +1,144 @@
+//! Results in French, for readers of the tables rather than the programs that
+//! read JSON. Riding names come from the French columns of the data itself,
+//! while parties and field names have their own translations here.
+
+use crate::config::Settings;
+use crate::format::label;
+use crate::{load_polls, Party};
+use clap::ArgEnum;
+use itertools::Itertools;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::ops::Not;
+
+/// The languages results can be read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Lang {
+    En,
+    Fr,
+}
+
+/// Field names and their French equivalents. Those not listed are left as
+/// they are.
+const FIELDS: &[(&str, &str)] = &[
+    ("advance", "anticipé"),
+    ("advance_votes", "votes_anticipés"),
+    ("after", "après"),
+    ("average", "moyenne"),
+    ("average_margin", "marge_moyenne"),
+    ("before", "avant"),
+    ("candidates", "candidats"),
+    ("change", "variation"),
+    ("changes", "changements"),
+    ("class", "catégorie"),
+    ("colour", "couleur"),
+    ("contested", "disputées"),
+    ("defeated", "défaits"),
+    ("difference", "écart"),
+    ("district", "circonscription_no"),
+    ("elected", "élu"),
+    ("electors", "électeurs"),
+    ("first_name", "prénom"),
+    ("flipped", "basculées"),
+    ("from", "de"),
+    ("government", "gouvernement"),
+    ("incumbent", "sortant"),
+    ("incumbents", "sortants"),
+    ("last_name", "nom"),
+    ("leader", "en_tête"),
+    ("lost", "perdues"),
+    ("lost_deposits", "cautionnements_perdus"),
+    ("magic_number", "seuil_majoritaire"),
+    ("majority", "majorité"),
+    ("margin", "marge"),
+    ("mean", "moyenne"),
+    ("median", "médiane"),
+    ("metric", "mesure"),
+    ("middle_name", "second_prénom"),
+    ("name", "nom_complet"),
+    ("number", "numéro"),
+    ("parties", "partis"),
+    ("party", "parti"),
+    ("placing", "rang"),
+    ("polls", "bureaux"),
+    ("rank", "rang"),
+    ("ratio", "proportion"),
+    ("rejected", "rejetés"),
+    ("results", "résultats"),
+    ("riding", "circonscription"),
+    ("ridings", "circonscriptions"),
+    ("runner_up", "deuxième"),
+    ("runner_up_votes", "votes_deuxième"),
+    ("seats", "sièges"),
+    ("shares", "parts"),
+    ("shortfall", "manque"),
+    ("special", "spéciaux"),
+    ("special_votes", "votes_spéciaux"),
+    ("station", "bureau"),
+    ("swing", "glissement"),
+    ("to", "à"),
+    ("total_votes", "total_des_votes"),
+    ("turnout", "participation"),
+    ("value", "valeur"),
+    ("winner", "gagnant"),
+    ("winner_votes", "votes_gagnant"),
+    ("wins", "victoires"),
+    ("won", "gagné"),
+    ("year", "année"),
+];
+
+/// A field's name in French, if it has one of its own.
+pub fn french(field: &str) -> Option<&'static str> {
+    FIELDS.iter().find(|(f, _)| *f == field).map(|(_, fr)| *fr)
+}
+
+/// How to put a result into another language.
+pub struct Translation {
+    fields: HashMap<&'static str, &'static str>,
+    /// Party and riding names, by their English ones.
+    names: HashMap<String, String>,
+}
+
+impl Translation {
+    /// French, with riding names as they were in the given year's data.
+    pub fn french(settings: &Settings, year: usize) -> Translation {
+        let parties = Party::all().map(|p| (label(&p), p.french()));
+        let ridings = load_polls(settings, year)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| p.riding_fr.is_empty().not())
+            .map(|p| (p.riding, p.riding_fr));
+
+        Translation {
+            fields: FIELDS.iter().copied().collect(),
+            names: parties.chain(ridings).collect(),
+        }
+    }
+
+    /// A result with its field names, parties and ridings translated. So are
+    /// the dotted field names that long format gives as each row's `metric`.
+    pub fn apply(&self, value: Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let map: Map<String, Value> = map
+                    .into_iter()
+                    .map(|(k, v)| match (k.as_str(), v) {
+                        ("metric", Value::String(m)) => {
+                            let m = m.split('.').map(|f| self.field(f)).join(".");
+                            (self.field(&k), Value::String(m))
+                        }
+                        (_, v) => (self.field(&k), self.apply(v)),
+                    })
+                    .collect();
+                Value::Object(map)
+            }
+            Value::Array(vs) => Value::Array(vs.into_iter().map(|v| self.apply(v)).collect()),
+            Value::String(s) => Value::String(self.names.get(&s).cloned().unwrap_or(s)),
+            v => v,
+        }
+    }
+
+    fn field(&self, name: &str) -> String {
+        self.fields.get(name).copied().unwrap_or(name).to_string()
+    }
+}
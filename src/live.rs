@@ -0,0 +1,84 @@
+//! Election-night results, as Elections Canada publishes them while the count
+//! is underway.
+//!
+//! The feed is a tab-separated table with a row per candidate, preceded by a
+//! title line and followed by footnotes. Each row is read as a single poll, so
+//! that every analysis works on it just as it would on the final results.
+
+use crate::{Ballots, Party, Poll};
+use serde::Deserialize;
+use std::io::{Error, ErrorKind};
+
+/// Where Elections Canada publishes its live results.
+pub const FEED: &str = "https://enr.elections.ca/DownloadResults.aspx";
+
+#[derive(Deserialize)]
+struct Row {
+    #[serde(rename = "Electoral district number - Numéro de la circonscription")]
+    district: u32,
+    #[serde(rename = "Electoral district name")]
+    riding: String,
+    #[serde(rename = "Surname - Nom de famille")]
+    last_name: String,
+    #[serde(rename = "Given name - Prénom")]
+    first_name: String,
+    #[serde(rename = "Middle name(s) - Autre(s) prénom(s)", default)]
+    middle_name: String,
+    #[serde(rename = "Political affiliation")]
+    party: Party,
+    #[serde(rename = "Votes obtained - Votes obtenus")]
+    votes: usize,
+    #[serde(rename = "Rejected ballots - Bulletins rejetés***", default)]
+    rejected: usize,
+}
+
+/// The current state of the feed, as is.
+pub fn download(url: &str) -> Result<String, Error> {
+    ureq::get(url).call().map_err(Error::other)?.into_string()
+}
+
+/// Read the feed's rows as if they were poll-by-poll data.
+pub fn read(body: &str) -> Result<Vec<Poll>, Error> {
+    let table = body
+        .find("Electoral district number")
+        .map(|start| &body[start..])
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No results table in the feed"))?;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .flexible(true)
+        .from_reader(table.as_bytes());
+    let headers = reader.headers()?.clone();
+
+    reader
+        .records()
+        // The footnotes below the table are single-column rows.
+        .filter(|r| r.as_ref().map_or(true, |r| r.len() == headers.len()))
+        .map(|r| {
+            let row: Row = r?.deserialize(Some(&headers))?;
+
+            let poll = Poll {
+                district: row.district,
+                riding: row.riding,
+                riding_fr: String::new(),
+                number: String::new(),
+                station: String::new(),
+                void: false,
+                not_held: false,
+                party: row.party,
+                last_name: row.last_name,
+                first_name: row.first_name,
+                middle_name: row.middle_name,
+                votes: row.votes,
+                incumbent: false,
+                // Nobody is declared elected on the night itself.
+                elected: false,
+                electors: 0,
+                rejected: row.rejected,
+                ballots: Ballots::default(),
+            };
+
+            Ok(poll.classify())
+        })
+        .collect()
+}
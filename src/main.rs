@@ -1,38 +1,567 @@
-use clap::{crate_version, ArgEnum, Clap};
+use aliases::Aliases;
+use chart::ChartArgs;
+use clap::{crate_version, AppSettings, ArgEnum, Clap, FromArgMatches, IntoApp};
+use classes::Classes;
+use config::{Config, Settings};
+use corrections::Corrections;
+use export::ExportArgs;
+use fetch::FetchArgs;
+use format::Format;
+use indicatif::{ProgressBar, ProgressStyle};
 use itertools::Itertools;
+use lang::{Lang, Translation};
+use output::{Envelope, Output};
+use privacy::SmallPolls;
+use redistribution::Redistribution;
+use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Error, ErrorKind, IsTerminal};
 use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::level_filters::LevelFilter;
+use tracing::{debug, info, warn, Level};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use validate::ValidateArgs;
 
-#[derive(Clap)]
-#[clap(author = "Colin Woodbury", version = crate_version!(), about = "Canadian Federal Election data")]
+mod alerts;
+mod aliases;
+mod cache;
+mod careers;
+mod chart;
+mod classes;
+mod config;
+mod corrections;
+mod export;
+mod fetch;
+mod format;
+mod geo;
+mod jurisdiction;
+mod lang;
+mod live;
+mod output;
+mod plugins;
+#[cfg(feature = "png")]
+mod png;
+mod privacy;
+mod redistribution;
+mod report;
+mod sample;
+mod schema;
+mod summary;
+mod tidy;
+mod transposed;
+mod validate;
+
+/// Victory margins below this count as close races.
+const CLOSE_MARGIN: f64 = 0.05;
+
+/// How many seconds to wait between each look at the live results feed.
+const LIVE_INTERVAL: u64 = 30;
+
+/// The election years for which data is supported, in any jurisdiction. See
+/// [`jurisdiction::Jurisdiction::years`] for those of each.
+const YEARS: &[&str] = &[
+    "2004", "2006", "2008", "2011", "2014", "2015", "2017", "2018", "2019", "2020", "2021", "2022",
+    "2024", "2025",
+];
+
+#[derive(Clap, Debug)]
+#[clap(
+    author = "Colin Woodbury",
+    version = crate_version!(),
+    about = "Canadian Federal Election data",
+    setting = AppSettings::SubcommandRequiredElseHelp,
+    after_help = "Use `election run <PRESET>` to run a named preset, or `election <COMMAND>` \
+                  to run an `election-<COMMAND>` plugin found on the PATH."
+)]
 struct Args {
-    /// Total votes and seats for every party.
-    #[clap(group = "choice", long, display_order = 1)]
-    total: bool,
+    #[clap(subcommand)]
+    command: Command,
 
-    /// Ridings where CON would have won if all PPC had voted CON.
-    #[clap(group = "choice", long, display_order = 1)]
-    conppc: bool,
+    /// Rerun the analysis whenever the data or config files change, sending
+    /// any alerts configured in the config file.
+    #[clap(long, global = true, display_order = 3)]
+    watch: bool,
 
-    /// Ridings ordered by margin of victory.
-    #[clap(group = "choice", long, display_order = 1)]
-    margins: bool,
+    /// A CSV classifying ridings, with a `class` column and either a
+    /// `district` or a `riding` column.
+    #[clap(
+        long,
+        global = true,
+        display_order = 2,
+        value_name = "FILE",
+        env = "ELECTION_CLASSES"
+    )]
+    classes: Option<PathBuf>,
+
+    /// A CSV transposing the results of older ridings onto the boundaries of a
+    /// later redistribution, with `from`, `to` and `share` columns, for
+    /// comparing elections on either side of it.
+    #[clap(
+        long,
+        global = true,
+        display_order = 2,
+        value_name = "FILE",
+        env = "ELECTION_REDISTRIBUTION"
+    )]
+    redistribution: Option<PathBuf>,
+
+    /// A CSV of corrected vote counts, as after judicial recounts, to use in
+    /// place of the published ones. Per-poll analyses are unaffected.
+    #[clap(
+        long,
+        global = true,
+        display_order = 2,
+        value_name = "FILE",
+        env = "ELECTION_CORRECTIONS"
+    )]
+    corrections: Option<PathBuf>,
+
+    /// Leave out the ballots cast at these kinds of poll.
+    #[clap(
+        long,
+        global = true,
+        display_order = 2,
+        arg_enum,
+        use_delimiter = true,
+        multiple_occurrences = true,
+        value_name = "KIND"
+    )]
+    exclude_polls: Vec<PollKind>,
+
+    /// Leave out these parties' candidates entirely, as if they hadn't run.
+    /// Winners and shares are worked out from whoever remains.
+    #[clap(
+        long,
+        global = true,
+        display_order = 2,
+        arg_enum,
+        case_insensitive = true,
+        use_delimiter = true,
+        multiple_occurrences = true,
+        value_name = "PARTY"
+    )]
+    exclude_party: Vec<Party>,
+
+    /// Only consider ridings of the given class.
+    #[clap(long, global = true, display_order = 2, requires = "classes")]
+    class: Option<String>,
+
+    /// Where to send the results: stdout, file://PATH, an http(s):// URL to
+    /// POST to, or s3://BUCKET/KEY.
+    #[clap(long, global = true, display_order = 3, value_name = "DEST")]
+    sink: Option<String>,
+
+    /// Write the results to a file, in the format its extension names unless
+    /// --format says otherwise.
+    #[clap(
+        long,
+        global = true,
+        display_order = 3,
+        value_name = "FILE",
+        conflicts_with = "sink"
+    )]
+    output: Option<PathBuf>,
+
+    /// Only list ridings whose names loosely match this, ignoring accents,
+    /// case and punctuation, like "trois rivieres".
+    #[clap(long, global = true, display_order = 3, value_name = "QUERY")]
+    riding_filter: Option<String>,
+
+    /// Only list ridings whose names match this regular expression.
+    #[clap(long, global = true, display_order = 3, value_name = "REGEX")]
+    riding_regex: Option<Regex>,
+
+    /// Only list candidates whose full names match this regular expression.
+    #[clap(long, global = true, display_order = 3, value_name = "REGEX")]
+    candidate_regex: Option<Regex>,
+
+    /// Sort a list of results by the given field, like `margin` or `votes`.
+    #[clap(long, global = true, display_order = 3, value_name = "FIELD")]
+    sort_by: Option<String>,
+
+    /// Sort --sort-by from the largest down.
+    #[clap(long, global = true, display_order = 3, requires = "sort-by")]
+    desc: bool,
+
+    /// Only give the first N results of a list.
+    #[clap(
+        long,
+        global = true,
+        display_order = 3,
+        visible_alias = "limit",
+        value_name = "N"
+    )]
+    top: Option<usize>,
+
+    /// Keep tiny polls from singling out their voters in by-poll results and
+    /// exported polls: leave out those with fewer than --small-poll-size
+    /// votes, or round every count to a multiple of it.
+    #[clap(long, global = true, display_order = 3, arg_enum, value_name = "MODE")]
+    small_polls: Option<privacy::Mode>,
+
+    /// The poll size that --small-polls works with.
+    #[clap(
+        long,
+        global = true,
+        display_order = 3,
+        value_name = "N",
+        default_value = "10"
+    )]
+    small_poll_size: usize,
+
+    /// Reshape the results into long format: one row per value, with the
+    /// riding, party and so on that identify it.
+    #[clap(long, global = true, display_order = 3)]
+    tidy: bool,
+
+    /// How to render the results: a table when printing to a terminal, and
+    /// JSON otherwise. Results that aren't a list of records are reshaped as
+    /// with --tidy to fit CSV, TSV and tables.
+    #[clap(long, global = true, display_order = 3, arg_enum)]
+    format: Option<Format>,
+
+    /// Print JSON even to a terminal.
+    #[clap(long, global = true, display_order = 3, conflicts_with = "format")]
+    json: bool,
 
-    /// How a given Party did in every riding.
-    #[clap(group = "choice", long, display_order = 1, arg_enum)]
-    party: Option<Party>,
+    /// Wrap the results with the year, command, tool version, a digest of
+    /// the data and the time they were computed. Implies JSON, though YAML
+    /// works too.
+    #[clap(long, global = true, display_order = 3)]
+    envelope: bool,
+
+    /// Whose elections to analyse: Canada's, or a province's. Provincial
+    /// results live in a directory of their own within the data directory.
+    #[clap(
+        long,
+        global = true,
+        display_order = 2,
+        arg_enum,
+        default_value = "federal",
+        env = "ELECTION_JURISDICTION"
+    )]
+    jurisdiction: jurisdiction::Kind,
+
+    /// Where to find the data, in place of the default of `./data`, falling
+    /// back to `~/.local/share/election`.
+    #[clap(
+        long,
+        global = true,
+        display_order = 2,
+        value_name = "DIR",
+        env = "ELECTION_DATA_DIR"
+    )]
+    data_dir: Option<PathBuf>,
+
+    /// Read poll-by-poll CSVs from stdin instead of the year's data directory.
+    /// Several files can simply be concatenated.
+    #[clap(long, global = true, display_order = 2, conflicts_with_all = &["cache", "watch"])]
+    stdin: bool,
+
+    /// Follow election-night results as they come in, rerunning the analysis
+    /// whenever they change and sending any alerts configured in the config
+    /// file.
+    #[clap(long, global = true, display_order = 3, conflicts_with_all = &["cache", "watch", "stdin"])]
+    live: bool,
+
+    /// Where --live finds the results.
+    #[clap(long, global = true, display_order = 3, value_name = "URL", default_value = live::FEED)]
+    feed: String,
+
+    /// Reuse the result of an identical earlier query if neither the data nor
+    /// any file it was read alongside, like --corrections or the config file,
+    /// has changed since.
+    #[clap(long, global = true, display_order = 3)]
+    cache: bool,
+
+    /// Parse the year's CSVs afresh, rather than reading the binary copy kept
+    /// from an earlier run.
+    #[clap(long, global = true, display_order = 3)]
+    no_cache: bool,
+
+    /// Leave tables uncoloured, as does setting `NO_COLOR`.
+    #[clap(long, global = true, display_order = 3)]
+    no_color: bool,
+
+    /// The language of tables and other output meant for reading. Field
+    /// names, parties and ridings are translated; JSON and the like aren't.
+    #[clap(
+        long,
+        global = true,
+        display_order = 3,
+        arg_enum,
+        default_value = "en",
+        env = "ELECTION_LANG"
+    )]
+    lang: Lang,
+
+    /// Log what was read and skipped to stderr. Repeat for more detail.
+    #[clap(short, long, global = true, display_order = 3, parse(from_occurrences))]
+    verbose: u8,
+
+    /// Log nothing but errors.
+    #[clap(
+        short,
+        long,
+        global = true,
+        display_order = 3,
+        conflicts_with = "verbose"
+    )]
+    quiet: bool,
 
     /// The election year to consider.
-    #[clap(long, display_order = 2, possible_values = &["2008", "2011", "2015", "2019"], default_value = "2019")]
+    #[clap(
+        long,
+        global = true,
+        display_order = 2,
+        possible_values = YEARS,
+        default_value = "2019",
+        env = "ELECTION_YEAR"
+    )]
     year: usize,
 }
 
+// The analyses, each with its own options.
+#[derive(Clap, Debug)]
+enum Command {
+    /// Total votes and seats for every party.
+    Totals,
+
+    /// Ridings that the given parties would have won had their votes been
+    /// combined, like `con,ppc`.
+    Combine {
+        #[clap(
+            arg_enum,
+            case_insensitive = true,
+            use_delimiter = true,
+            min_values = 2,
+            required = true,
+            value_name = "PARTIES"
+        )]
+        parties: Vec<Party>,
+    },
+
+    /// Ridings ordered by margin of victory.
+    Margins {
+        /// Order margins by raw votes rather than by share of the vote.
+        #[clap(long)]
+        absolute: bool,
+    },
+
+    /// How the given parties did in every riding, one after another, like
+    /// `ndp grn` or `ndp,grn`.
+    Party {
+        #[clap(
+            arg_enum,
+            case_insensitive = true,
+            use_delimiter = true,
+            required = true,
+            value_name = "PARTIES"
+        )]
+        parties: Vec<Party>,
+    },
+
+    /// Number of candidates who ran in each riding.
+    Candidates,
+
+    /// Re-election rates of incumbents, and who won the open seats.
+    Incumbents,
+
+    /// Ridings whose computed winner differs from the officially elected one.
+    Verify,
+
+    /// Every run of candidates matching the given name, across all years.
+    Candidate { name: String },
+
+    /// Candidates who ran in several elections, and how they did each time.
+    Careers,
+
+    /// Winning streaks and comebacks of candidates who ran several times.
+    Streaks,
+
+    /// Candidates who ran for different parties in different elections.
+    Switchers,
+
+    /// Ridings whose winning party changed between two elections.
+    Compare {
+        #[clap(possible_values = YEARS)]
+        from: usize,
+        #[clap(possible_values = YEARS)]
+        to: usize,
+    },
+
+    /// Each party's change in vote share per riding between two elections.
+    Swing {
+        #[clap(possible_values = YEARS)]
+        from: usize,
+        #[clap(possible_values = YEARS)]
+        to: usize,
+    },
+
+    /// The winner, margin, turnout and party shares of a riding in every year.
+    History { riding: String },
+
+    /// Everything about a single riding in the given year.
+    Riding { riding: String },
+
+    /// How the Bloc fared against the federalist parties in Quebec.
+    Quebec,
+
+    /// Special-ballot (mail-in etc.) party shares against in-person ones.
+    Special,
+
+    /// How each party's support in advance polls differed from election day,
+    /// most divergent ridings first.
+    Advance,
+
+    /// Seats and vote shares per party within each class of riding given by
+    /// --classes.
+    ByClass,
+
+    /// Ridings whose election-day leader lost once advance and special
+    /// ballots were counted.
+    LateFlips,
+
+    /// Voters who would have had to switch (or stay home) to change each
+    /// riding's winner.
+    Decisive,
+
+    /// Binned vote shares of a given Party across the ridings it contested.
+    Histogram {
+        #[clap(arg_enum, case_insensitive = true)]
+        party: Party,
+
+        /// The width of each bin, as a ratio of the vote.
+        #[clap(long, default_value = "0.05")]
+        bin_width: f64,
+    },
+
+    /// Candidates who fell below the deposit-refund threshold.
+    Deposits {
+        /// The share of the vote below which a candidate lost their deposit.
+        #[clap(long, default_value = "0.1")]
+        threshold: f64,
+    },
+
+    /// The strongest and weakest ridings of a given Party, by vote share.
+    Heartlands {
+        #[clap(arg_enum, case_insensitive = true)]
+        party: Party,
+
+        /// How many ridings to list at each end.
+        #[clap(long, default_value = "10")]
+        count: usize,
+    },
+
+    /// Every candidate's result in every individual poll, rather than fused
+    /// per riding.
+    ByPoll,
+
+    /// The spread of turnout across each riding's polls, with its highest
+    /// and lowest turnout polls.
+    PollTurnout,
+
+    /// The rate of rejected ballots in each riding, and whether they
+    /// outnumbered the margin of victory.
+    Rejected,
+
+    /// Provinces ranked by how closely contested their seats were.
+    Competitiveness,
+
+    /// How fragmented the vote was in each riding, most fragmented first.
+    Fragmentation,
+
+    /// Where independent and unaffiliated candidates ran, and how they did.
+    Independents,
+
+    /// Ridings where a given Party's vote exceeded the winner's margin.
+    Spoilers {
+        #[clap(arg_enum, case_insensitive = true)]
+        party: Party,
+    },
+
+    /// How many ridings each party contested, which it skipped, and its
+    /// average share where it ran.
+    Coverage,
+
+    /// Each party's vote share per province relative to its national share.
+    Fingerprint,
+
+    /// Each party's average and median victory margin in the seats it won.
+    CaucusMargins,
+
+    /// By-election results from `data/byelections`, with the seats that
+    /// changed hands between general elections.
+    Byelections,
+
+    /// Elections where the party with the most votes didn't win the most
+    /// seats.
+    Inversions,
+
+    /// Seat blocks in seating order, for drawing a hemicycle chart.
+    Hemicycle,
+
+    /// Seats won with a majority of the vote versus a mere plurality.
+    Majorities,
+
+    /// Download a year's poll-by-poll results from Elections Canada into its
+    /// data directory.
+    Fetch(FetchArgs),
+
+    /// Check a year's data, or any directory of it, for problems.
+    Validate(ValidateArgs),
+
+    /// Draw the year's results as a chart.
+    Chart(ChartArgs),
+
+    /// Write the year's results in a form other tools read directly.
+    Export(ExportArgs),
+
+    /// A single HTML page of the year's headline results.
+    Report,
+
+    /// JSON Schemas of the results of every command, or of the one named.
+    Schema { name: Option<String> },
+
+    /// Copy a random, anonymized handful of the year's ridings into the given
+    /// directory, laid out like the data directory so that it can stand in
+    /// for one.
+    Sample {
+        dir: PathBuf,
+
+        /// How many ridings to take.
+        #[clap(long, default_value = "10")]
+        ridings: usize,
+
+        /// The seed that decides which ridings are taken.
+        #[clap(long, default_value = "0")]
+        seed: u64,
+    },
+
+    /// Anything else is the name of a plugin, followed by its arguments.
+    #[clap(external_subcommand)]
+    Plugin(Vec<String>),
+}
+
 #[derive(Debug)]
 struct Riding {
     name: String,
+    district: u32,
+    /// Registered electors across all polls.
+    electors: usize,
+    /// Ballots rejected across all polls.
+    rejected: usize,
+    /// Only one candidate per party fits here, so several independents in
+    /// one riding are fused. See `unaffiliated` for them individually.
     candidates: HashMap<Party, Candidate>,
+    /// Every independent and unaffiliated candidate, unfused.
+    unaffiliated: Vec<(Party, Candidate)>,
 }
 
 impl Riding {
@@ -41,25 +570,126 @@ impl Riding {
         party == &self.winner()
     }
 
-    /// The victories party in this riding.
+    /// The victorious party in this riding. Independents are judged one by
+    /// one, so several of them can't win a seat none of them did.
     fn winner(&self) -> Party {
-        self.candidates
-            .iter()
+        self.victor().0.clone()
+    }
+
+    /// The candidate who won this riding, and their party.
+    fn victor(&self) -> (&Party, &Candidate) {
+        self.individuals()
             .max_by(|(_, a), (_, b)| a.votes.cmp(&b.votes))
             .unwrap()
-            .0
-            .clone()
     }
 
-    /// The margin of victory for this `Riding`.
-    fn victory_margin(&self) -> f32 {
-        let mut votes: Vec<_> = self.candidates.values().map(|c| c.votes).collect();
-        votes.sort_by(|a, b| b.cmp(&a));
-        let total_votes: usize = votes.iter().sum();
-        let winner = votes[0] as f32;
-        let second = votes[1] as f32;
+    /// Was this the candidate who won the riding? Parties alone can't say, as
+    /// independents share one.
+    fn is_victor(&self, party: &Party, candidate: &Candidate) -> bool {
+        let (p, c) = self.victor();
+        p == party && c.name() == candidate.name()
+    }
+
+    /// The candidate that Elections Canada declared elected, if the data says
+    /// so.
+    fn official_winner(&self) -> Option<(&Party, &Candidate)> {
+        self.individuals().find(|(_, c)| c.elected)
+    }
+
+    /// Candidates in descending order of votes received, independents each in
+    /// their own place.
+    fn placings(&self) -> Vec<(&Party, &Candidate)> {
+        let mut placings: Vec<_> = self.individuals().collect();
+        placings.sort_by_key(|(_, c)| Reverse(c.votes));
+        placings
+    }
+
+    /// The party that came second in this riding, if anyone did.
+    fn runner_up(&self) -> Option<Party> {
+        self.placings().get(1).map(|(p, _)| (*p).clone())
+    }
+
+    /// The fewest voters who would have had to switch from the winner to the
+    /// runner-up to change the result.
+    fn decisive_votes(&self) -> usize {
+        match self.placings().as_slice() {
+            [(_, w), (_, r), ..] => (w.votes - r.votes) / 2 + 1,
+            _ => 0,
+        }
+    }
+
+    /// The fewest of the winner's voters who would have had to stay home to
+    /// change the result.
+    fn abstentions_to_flip(&self) -> usize {
+        match self.placings().as_slice() {
+            [(_, w), (_, r), ..] => w.votes - r.votes + 1,
+            _ => 0,
+        }
+    }
+
+    /// The Herfindahl-Hirschman index of the candidates' vote shares: 1 when
+    /// one candidate got every vote, approaching 0 as the vote fragments.
+    fn concentration(&self) -> f64 {
+        let total = self.total_votes() as f64;
+
+        self.individuals()
+            .map(|(_, c)| (c.votes as f64 / total).powi(2))
+            .sum()
+    }
+
+    /// Every candidate, without fusing independents together.
+    fn individuals(&self) -> impl Iterator<Item = (&Party, &Candidate)> {
+        self.candidates
+            .iter()
+            .filter(|(p, _)| !p.is_unaffiliated())
+            .chain(self.unaffiliated.iter().map(|(p, c)| (p, c)))
+    }
+
+    /// The margin of victory for this `Riding`, as a share of the vote.
+    fn victory_margin(&self) -> f64 {
+        self.vote_margin() as f64 / self.total_votes() as f64
+    }
+
+    /// The margin of victory for this `Riding`, in raw votes.
+    fn vote_margin(&self) -> usize {
+        match self.placings().as_slice() {
+            [(_, w), (_, r), ..] => w.votes - r.votes,
+            // Unopposed, as when every rival was excluded, the winner's every
+            // vote is their margin.
+            [(_, w)] => w.votes,
+            [] => 0,
+        }
+    }
+
+    /// The given [`Party`]'s share of the vote in this `Riding`, if it ran.
+    fn share(&self, party: &Party) -> Option<f64> {
+        self.candidates
+            .get(party)
+            .map(|c| c.votes as f64 / self.total_votes() as f64)
+    }
+
+    /// The ratio of ballots cast (including rejected ones) to registered
+    /// electors, if the number of electors is known.
+    fn turnout(&self) -> Option<f64> {
+        (self.electors > 0)
+            .then(|| (self.total_votes() + self.rejected) as f64 / self.electors as f64)
+    }
+
+    /// The province this riding is in.
+    fn province(&self) -> Option<Province> {
+        Province::from_district(self.district)
+    }
 
-        (winner - second) / total_votes as f32
+    /// The combined votes of the given parties in this riding.
+    fn combined_votes<F>(&self, pred: F) -> usize
+    where
+        F: Fn(&Party) -> bool,
+    {
+        self.candidates
+            .iter()
+            .filter(|(p, _)| pred(p))
+            .map(|(_, c)| c.votes)
+            .sum()
     }
 
     /// The total votes in this `Riding`.
@@ -68,34 +698,259 @@ impl Riding {
     }
 }
 
-#[derive(Debug)]
+/// A riding as it existed in a particular election. District numbers are only
+/// stable within a single representation order, so the year is needed to say
+/// which riding a number actually refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, JsonSchema)]
+struct RidingId {
+    district: u32,
+    year: usize,
+}
+
+impl RidingId {
+    /// The year of the representation order whose boundaries were in force
+    /// for this election.
+    fn representation_order(&self) -> usize {
+        if self.year < 2015 {
+            2003
+        } else {
+            2013
+        }
+    }
+
+    /// Do these refer to the same riding, perhaps in different elections?
+    /// Ridings from different representation orders never correspond, since
+    /// their boundaries (and numbering) were redrawn.
+    fn corresponds_to(&self, other: &RidingId) -> bool {
+        self.district == other.district
+            && self.representation_order() == other.representation_order()
+    }
+}
+
+#[derive(Debug, Clone)]
 struct Candidate {
     last_name: String,
     first_name: String,
+    middle_name: String,
     votes: usize,
+    ballots: Ballots,
+    incumbent: bool,
+    elected: bool,
+}
+
+impl Candidate {
+    fn name(&self) -> String {
+        full_name(&self.first_name, &self.middle_name, &self.last_name)
+    }
+
+    /// Fuse two candidates of the same party in the same riding, which only
+    /// happens for independents.
+    fn fuse(mut self, other: Candidate) -> Candidate {
+        self.votes += other.votes;
+        self.ballots.fuse(other.ballots);
+        self.incumbent |= other.incumbent;
+        self.elected |= other.elected;
+        self
+    }
+}
+
+/// The kind of polling station at which ballots were cast, as told by its
+/// number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+enum PollKind {
+    /// A regular election-day poll.
+    Ordinary,
+    /// Advance polls are numbered in the 600s.
+    Advance,
+    /// Mobile polls, which visit care homes and the like, are in the 500s.
+    Mobile,
+    /// Special Voting Rules ballots (mail-in, military, abroad and so on) are
+    /// reported as `S/R 1` and `S/R 2`.
+    Special,
+}
+
+impl PollKind {
+    fn from_number(number: &str) -> PollKind {
+        let number = number.trim();
+
+        if number.starts_with("S/R") {
+            PollKind::Special
+        } else {
+            let digits: String = number.chars().take_while(|c| c.is_ascii_digit()).collect();
+
+            match digits.parse::<usize>() {
+                Ok(500..=599) => PollKind::Mobile,
+                Ok(600..=699) => PollKind::Advance,
+                _ => PollKind::Ordinary,
+            }
+        }
+    }
+}
+
+/// Votes broken down by the kind of poll they were cast at.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Ballots {
+    ordinary: usize,
+    advance: usize,
+    mobile: usize,
+    special: usize,
+}
+
+impl Ballots {
+    fn add(&mut self, kind: PollKind, votes: usize) {
+        match kind {
+            PollKind::Ordinary => self.ordinary += votes,
+            PollKind::Advance => self.advance += votes,
+            PollKind::Mobile => self.mobile += votes,
+            PollKind::Special => self.special += votes,
+        }
+    }
+
+    fn fuse(&mut self, other: Ballots) {
+        self.ordinary += other.ordinary;
+        self.advance += other.advance;
+        self.mobile += other.mobile;
+        self.special += other.special;
+    }
+
+    /// Ballots cast on election day itself.
+    fn election_day(&self) -> usize {
+        self.ordinary + self.mobile
+    }
+
+    /// Every ballot that was cast at a polling station.
+    fn in_person(&self) -> usize {
+        self.ordinary + self.advance + self.mobile
+    }
 }
 
 /// A particular poll within a riding. We expect an entry per party.
-#[derive(Debug, PartialEq, Eq, Deserialize, Clone)]
+///
+/// The French downloads carry the same columns with each header's halves
+/// swapped, hence the aliases.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 struct Poll {
+    #[serde(rename = "Electoral District Number/Numéro de circonscription")]
+    #[serde(alias = "Numéro de circonscription/Electoral District Number")]
+    district: u32,
     #[serde(rename = "Electoral District Name_English/Nom de circonscription_Anglais")]
+    #[serde(alias = "Nom de circonscription_Anglais/Electoral District Name_English")]
     riding: String,
+    #[serde(rename = "Electoral District Name_French/Nom de circonscription_Français")]
+    #[serde(alias = "Nom de circonscription_Français/Electoral District Name_French")]
+    #[serde(default)]
+    riding_fr: String,
+    #[serde(rename = "Polling Station Number/Numéro du bureau de scrutin", default)]
+    #[serde(alias = "Numéro du bureau de scrutin/Polling Station Number")]
+    number: String,
+    #[serde(rename = "Polling Station Name/Nom du bureau de scrutin", default)]
+    #[serde(alias = "Nom du bureau de scrutin/Polling Station Name")]
+    station: String,
+    /// Polls whose area turned out to have no electors.
+    #[serde(rename = "Void Poll Indicator/Indicateur de bureau supprimé")]
+    #[serde(alias = "Indicateur de bureau supprimé/Void Poll Indicator")]
+    #[serde(default, deserialize_with = "yes_no", serialize_with = "y_n")]
+    void: bool,
+    #[serde(rename = "No Poll Held Indicator/Indicateur de bureau sans scrutin")]
+    #[serde(alias = "Indicateur de bureau sans scrutin/No Poll Held Indicator")]
+    #[serde(default, deserialize_with = "yes_no", serialize_with = "y_n")]
+    not_held: bool,
     #[serde(rename = "Political Affiliation Name_English/Appartenance politique_Anglais")]
+    #[serde(alias = "Appartenance politique_Anglais/Political Affiliation Name_English")]
     party: Party,
     #[serde(rename = "Candidate’s Family Name/Nom de famille du candidat")]
+    #[serde(alias = "Nom de famille du candidat/Candidate’s Family Name")]
     #[serde(alias = "Candidate's Family Name/Nom de famille du candidat")]
+    #[serde(alias = "Nom de famille du candidat/Candidate's Family Name")]
     last_name: String,
     #[serde(rename = "Candidate’s First Name/Prénom du candidat")]
+    #[serde(alias = "Prénom du candidat/Candidate’s First Name")]
     #[serde(alias = "Candidate's First Name/Prénom du candidat")]
+    #[serde(alias = "Prénom du candidat/Candidate's First Name")]
     first_name: String,
+    #[serde(rename = "Candidate’s Middle Name/Second prénom du candidat", default)]
+    #[serde(alias = "Second prénom du candidat/Candidate’s Middle Name")]
+    #[serde(alias = "Candidate's Middle Name/Second prénom du candidat")]
+    #[serde(alias = "Second prénom du candidat/Candidate's Middle Name")]
+    middle_name: String,
     #[serde(rename = "Candidate Poll Votes Count/Votes du candidat pour le bureau")]
+    #[serde(alias = "Votes du candidat pour le bureau/Candidate Poll Votes Count")]
     votes: usize,
+    #[serde(rename = "Incumbent Indicator/Indicateur_Candidat sortant")]
+    #[serde(alias = "Indicateur_Candidat sortant/Incumbent Indicator")]
+    #[serde(default, deserialize_with = "yes_no", serialize_with = "y_n")]
+    incumbent: bool,
+    #[serde(rename = "Elected Candidate Indicator/Indicateur du candidat élu")]
+    #[serde(alias = "Indicateur du candidat élu/Elected Candidate Indicator")]
+    #[serde(default, deserialize_with = "yes_no", serialize_with = "y_n")]
+    elected: bool,
+    #[serde(rename = "Electors for Polling Station/Électeurs du bureau", default)]
+    #[serde(alias = "Électeurs du bureau/Electors for Polling Station")]
+    electors: usize,
+    #[serde(
+        rename = "Rejected Ballots for Polling Station/Bulletins rejetés du bureau",
+        default
+    )]
+    #[serde(alias = "Bulletins rejetés du bureau/Rejected Ballots for Polling Station")]
+    rejected: usize,
+    #[serde(skip)]
+    ballots: Ballots,
+}
+
+/// Elections Canada marks boolean columns with `Y` and `N`.
+fn yes_no<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    Ok(s.trim().eq_ignore_ascii_case("Y"))
+}
+
+/// The reverse of [`yes_no`], for writing polls back out.
+fn y_n<S>(value: &bool, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(if *value { "Y" } else { "N" })
 }
 
 impl Poll {
+    fn name(&self) -> String {
+        full_name(&self.first_name, &self.middle_name, &self.last_name)
+    }
+
+    /// Settle each part of the name into a consistent form.
+    fn tidy_names(mut self) -> Poll {
+        self.last_name = proper_name(&self.last_name);
+        self.first_name = proper_name(&self.first_name);
+        self.middle_name = proper_name(&self.middle_name);
+        self
+    }
+
+    /// Note which kind of poll this poll's votes were cast at, so that the
+    /// distinction survives fusing.
+    fn classify(mut self) -> Poll {
+        self.ballots.add(self.kind(), self.votes);
+        self
+    }
+
+    fn kind(&self) -> PollKind {
+        PollKind::from_number(&self.number)
+    }
+
+    /// Replace the candidate's total. Corrections don't say which kind of poll
+    /// the difference came from, so it's put down to ordinary polls.
+    fn correct(&mut self, votes: usize) {
+        self.ballots.ordinary = (self.ballots.ordinary + votes).saturating_sub(self.votes);
+        self.votes = votes;
+    }
+
     /// Fuse two polls from the (hopefully) same riding.
     fn fuse(mut self, other: Poll) -> Poll {
         self.votes += other.votes;
+        self.ballots.fuse(other.ballots);
+        self.electors += other.electors;
+        self.rejected += other.rejected;
         self
     }
 }
@@ -110,7 +965,10 @@ impl Ord for Poll {
     fn cmp(&self, other: &Self) -> Ordering {
         match self.riding.cmp(&other.riding) {
             Ordering::Equal => match self.party.cmp(&other.party) {
-                Ordering::Equal => self.last_name.cmp(&other.last_name),
+                Ordering::Equal => match self.last_name.cmp(&other.last_name) {
+                    Ordering::Equal => self.first_name.cmp(&other.first_name),
+                    o => o,
+                },
                 o => o,
             },
             o => o,
@@ -119,31 +977,42 @@ impl Ord for Poll {
 }
 
 /// A candidate's political party.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Clone, ArgEnum)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, JsonSchema, Deserialize, Clone, ArgEnum,
+)]
 enum Party {
     #[serde(rename = "Liberal")]
     LIB,
-    #[serde(rename = "Conservative")]
+    #[serde(rename = "Conservative", alias = "Conservative Party of Canada")]
     CON,
-    #[serde(rename(
-        deserialize = "NDP-New Democratic Party",
-        serialize = "New Democratic Party"
-    ))]
+    #[serde(
+        rename(
+            deserialize = "NDP-New Democratic Party",
+            serialize = "New Democratic Party"
+        ),
+        alias = "New Democratic Party"
+    )]
     NDP,
     #[serde(rename = "Bloc Québécois")]
     BLQ,
     #[serde(rename = "Green Party")]
     GRN,
-    #[serde(rename = "People's Party")]
+    #[serde(rename = "People's Party", alias = "People's Party - PPC")]
     PPC,
-    #[serde(rename = "Independent", alias = "No Affiliation")]
+    #[serde(rename = "Independent")]
     IND,
+    /// Unlike independents, these candidates didn't ask for any label on the
+    /// ballot.
+    #[serde(rename = "No Affiliation")]
+    NOA,
     // --- Small parties --- //
     #[serde(rename = "Libertarian")]
     LTN,
     #[serde(
         rename(deserialize = "Parti Rhinocéros Party", serialize = "Rhinoceros Party"),
         alias = "Rhinoceros",
+        alias = "Rhinoceros Party",
         alias = "neorhino.ca"
     )]
     RIN,
@@ -158,14 +1027,24 @@ enum Party {
     AAE,
     #[serde(rename = "Democratic Advancement")]
     DAD,
-    #[serde(rename(serialize = "Alliance of the North"))]
+    #[serde(
+        rename(serialize = "Alliance of the North"),
+        alias = "Alliance of the North"
+    )]
     ATN,
-    #[serde(rename(
-        deserialize = "Forces et Démocratie - Allier les forces de nos régions",
-        serialize = "Forces et Démocratie"
-    ))]
+    #[serde(
+        rename(
+            deserialize = "Forces et Démocratie - Allier les forces de nos régions",
+            serialize = "Forces et Démocratie"
+        ),
+        alias = "Forces et Démocratie"
+    )]
     FED,
-    #[serde(rename(deserialize = "VCP", serialize = "Veteran's Coalition"))]
+    #[serde(
+        rename(deserialize = "VCP", serialize = "Veteran's Coalition"),
+        alias = "Veterans Coalition Party",
+        alias = "Veteran's Coalition"
+    )]
     VCP,
     #[serde(rename = "Christian Heritage Party", alias = "CHP Canada")]
     CHP,
@@ -181,7 +1060,8 @@ enum Party {
     MXL,
     #[serde(
         rename(deserialize = "UPC", serialize = "United Party of Canada"),
-        alias = "United Party"
+        alias = "United Party",
+        alias = "United Party of Canada"
     )]
     UPC,
     #[serde(
@@ -191,14 +1071,20 @@ enum Party {
     PIR,
     #[serde(rename = "Radical Marijuana")]
     RMJ,
-    #[serde(rename(deserialize = "PC Party", serialize = "Progressive Canadian Party"))]
+    #[serde(
+        rename(deserialize = "PC Party", serialize = "Progressive Canadian Party"),
+        alias = "Progressive Canadian Party"
+    )]
     PCP,
     #[serde(rename = "Stop Climate Change")]
     SCC,
-    #[serde(rename(
-        deserialize = "CFF - Canada's Fourth Front",
-        serialize = "Canada's Fourth Front"
-    ))]
+    #[serde(
+        rename(
+            deserialize = "CFF - Canada's Fourth Front",
+            serialize = "Canada's Fourth Front"
+        ),
+        alias = "Canada's Fourth Front"
+    )]
     CFF,
     #[serde(rename = "Nationalist")]
     NAT,
@@ -206,237 +1092,3705 @@ enum Party {
     SNR,
     #[serde(rename = "Canada Party")]
     CAD,
-    #[serde(rename(serialize = "Canadian Action Party"))]
+    #[serde(
+        rename(serialize = "Canadian Action Party"),
+        alias = "Canadian Action Party"
+    )]
     CAP,
     #[serde(rename = "The Bridge")]
     TBR,
     PACT,
-    #[serde(rename(serialize = "Western Block Party"))]
+    #[serde(
+        rename(serialize = "Western Block Party"),
+        alias = "Western Block Party"
+    )]
     WBP,
-    #[serde(rename(serialize = "First Peoples National Party"))]
+    #[serde(
+        rename(serialize = "First Peoples National Party"),
+        alias = "First Peoples National Party"
+    )]
     FPNP,
     #[serde(rename = "Work Less Party")]
     WLP,
-    #[serde(rename(serialize = "People's Political Power"))]
+    #[serde(
+        rename(serialize = "People's Political Power"),
+        alias = "People's Political Power"
+    )]
     PPP,
-    #[serde(rename(
-        deserialize = "NL First Party",
-        serialize = "Newfoundland and Labrador First"
-    ))]
+    #[serde(
+        rename(
+            deserialize = "NL First Party",
+            serialize = "Newfoundland and Labrador First"
+        ),
+        alias = "Newfoundland and Labrador First"
+    )]
     NLF,
+    // --- First ran in 2021 --- //
+    #[serde(rename = "Free Party Canada")]
+    FPC,
+    #[serde(rename = "Maverick Party")]
+    MAV,
+    #[serde(rename = "Centrist Party")]
+    CEN,
+    #[serde(rename = "Marijuana Party", alias = "Marijuana")]
+    MJP,
+    // --- Predecessors of the Conservatives, seen in by-elections before the
+    // 2003 merger --- //
+    #[serde(
+        rename = "Progressive Conservative",
+        alias = "Progressive Conservative Party"
+    )]
+    PCO,
+    #[serde(
+        rename = "Canadian Alliance",
+        alias = "Canadian Reform Conservative Alliance"
+    )]
+    ALL,
+    // --- Provincial parties without a federal counterpart --- //
+    #[serde(rename = "Coalition Avenir Québec")]
+    CAQ,
+    #[serde(rename = "Québec solidaire")]
+    QS,
+    #[serde(rename = "Parti Québécois")]
+    PQ,
+    #[serde(rename = "BC United", alias = "BC Liberal Party")]
+    BCU,
+    /// Any provincial party that its jurisdiction doesn't list. Like
+    /// independents, its candidates are kept apart rather than fused.
+    #[serde(rename = "Other")]
+    OTH,
+}
+
+impl Party {
+    /// Every party there is.
+    fn all() -> impl Iterator<Item = Party> {
+        Party::VARIANTS
+            .iter()
+            .filter_map(|v| Party::from_str(v, false).ok())
+    }
+
+    /// Is this one of the small parties (or an independent) that rarely
+    /// contests more than a handful of ridings?
+    fn is_fringe(&self) -> bool {
+        !matches!(
+            self,
+            Party::LIB
+                | Party::CON
+                | Party::NDP
+                | Party::BLQ
+                | Party::GRN
+                | Party::PPC
+                | Party::PCO
+                | Party::CAQ
+                | Party::QS
+                | Party::PQ
+                | Party::BCU
+        )
+    }
+
+    /// Is this an independent or unaffiliated candidate rather than a party?
+    /// Those of unlisted provincial parties count too, since they're no one
+    /// party.
+    fn is_unaffiliated(&self) -> bool {
+        matches!(self, Party::IND | Party::NOA | Party::OTH)
+    }
+
+    /// Is this one of the national parties that contest Quebec alongside the
+    /// Bloc?
+    fn is_federalist(&self) -> bool {
+        matches!(
+            self,
+            Party::LIB | Party::CON | Party::NDP | Party::GRN | Party::PPC
+        )
+    }
+
+    /// The colour the party is usually drawn in, as a hex RGB code.
+    fn colour(&self) -> &'static str {
+        match self {
+            Party::LIB => "#D71920",
+            Party::CON => "#1A4782",
+            Party::NDP => "#F37021",
+            Party::BLQ => "#33B2CC",
+            Party::GRN => "#3D9B35",
+            Party::PPC => "#4E2E87",
+            Party::PCO => "#1A4782",
+            Party::CAQ => "#00A7E1",
+            Party::QS => "#FF5605",
+            Party::PQ => "#004C9D",
+            Party::BCU => "#0F4C81",
+            _ => "#AAAAAA",
+        }
+    }
+
+    /// The party's colour as red, green and blue.
+    fn rgb(&self) -> (u8, u8, u8) {
+        let hex = self.colour().trim_start_matches('#');
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+
+        (channel(0), channel(2), channel(4))
+    }
+
+    /// The party's name in French, where it goes by another one.
+    fn french(&self) -> String {
+        let name = match self {
+            Party::LIB => "Parti libéral",
+            Party::CON => "Parti conservateur",
+            Party::NDP => "Nouveau Parti démocratique",
+            Party::GRN => "Parti vert",
+            Party::PPC => "Parti populaire",
+            Party::IND => "Indépendant",
+            Party::NOA => "Aucune appartenance",
+            Party::LTN => "Parti libertarien",
+            Party::RIN => "Parti Rhinocéros",
+            Party::APP => "Parti pour la Protection des Animaux",
+            Party::CHP => "Parti de l'Héritage Chrétien",
+            Party::COM => "Parti communiste",
+            Party::MXL => "Marxiste-Léniniste",
+            Party::PIR => "Parti Pirate",
+            Party::MJP => "Parti Marijuana",
+            Party::PCO => "Progressiste-conservateur",
+            Party::ALL => "Alliance canadienne",
+            Party::OTH => "Autre",
+            _ => return format::label(self),
+        };
+
+        name.to_string()
+    }
+
+    /// Where the party sits in the House, from left to right. Independents and
+    /// small parties sit together at the far end.
+    fn seating(&self) -> usize {
+        match self {
+            Party::NDP => 0,
+            Party::GRN => 1,
+            Party::BLQ => 2,
+            Party::LIB => 3,
+            Party::CON => 4,
+            Party::PPC => 5,
+            _ => 6,
+        }
+    }
+}
+
+/// A province or territory, as encoded in the first two digits of an electoral
+/// district number.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, JsonSchema, Clone, Copy, ArgEnum,
+)]
+enum Province {
+    #[serde(rename = "Newfoundland and Labrador")]
+    NL,
+    #[serde(rename = "Prince Edward Island")]
+    PE,
+    #[serde(rename = "Nova Scotia")]
+    NS,
+    #[serde(rename = "New Brunswick")]
+    NB,
+    #[serde(rename = "Quebec")]
+    QC,
+    #[serde(rename = "Ontario")]
+    ON,
+    #[serde(rename = "Manitoba")]
+    MB,
+    #[serde(rename = "Saskatchewan")]
+    SK,
+    #[serde(rename = "Alberta")]
+    AB,
+    #[serde(rename = "British Columbia")]
+    BC,
+    #[serde(rename = "Yukon")]
+    YT,
+    #[serde(rename = "Northwest Territories")]
+    NT,
+    #[serde(rename = "Nunavut")]
+    NU,
+}
+
+impl Province {
+    /// The province a district number belongs to.
+    fn from_district(district: u32) -> Option<Province> {
+        match district / 1000 {
+            10 => Some(Province::NL),
+            11 => Some(Province::PE),
+            12 => Some(Province::NS),
+            13 => Some(Province::NB),
+            24 => Some(Province::QC),
+            35 => Some(Province::ON),
+            46 => Some(Province::MB),
+            47 => Some(Province::SK),
+            48 => Some(Province::AB),
+            59 => Some(Province::BC),
+            60 => Some(Province::YT),
+            61 => Some(Province::NT),
+            62 => Some(Province::NU),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct VoteCount {
     party: Party,
     votes: usize,
-    ratio: f32,
+    ratio: f64,
+    seats: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Government {
+    /// The party with the most seats.
+    party: Party,
     seats: usize,
+    /// The seats needed to command a majority of the House.
+    magic_number: usize,
+    majority: bool,
+    /// How many seats the party fell short of a majority.
+    shortfall: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Totals {
+    government: Option<Government>,
+    parties: Vec<VoteCount>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct ComboVictory {
     riding: String,
+    district: u32,
     winner: Party,
     winner_votes: usize,
-    con_ppc_votes: usize,
+    combined_votes: usize,
     difference: usize,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct VictoryMargin {
     riding: String,
+    district: u32,
     winner: Party,
-    margin: f32,
+    margin: f64,
+    votes: usize,
+}
+
+#[derive(Clone, Serialize, JsonSchema)]
+struct PartyResults {
+    riding: String,
+    district: u32,
+    party: Party,
+    name: String,
+    last_name: String,
+    first_name: String,
+    votes: usize,
+    ratio: f64,
+    won: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PartyIncumbents {
+    party: Party,
+    incumbents: usize,
+    reelected: usize,
+    defeated: usize,
+    rate: f64,
+    open_seats_won: usize,
+}
+
+impl PartyIncumbents {
+    fn new(party: Party) -> PartyIncumbents {
+        PartyIncumbents {
+            party,
+            incumbents: 0,
+            reelected: 0,
+            defeated: 0,
+            rate: 0.0,
+            open_seats_won: 0,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DefeatedIncumbent {
+    riding: String,
+    district: u32,
+    party: Party,
+    name: String,
+    last_name: String,
+    first_name: String,
+    votes: usize,
+    winner: Party,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Incumbency {
+    year: usize,
+    parties: Vec<PartyIncumbents>,
+    defeated: Vec<DefeatedIncumbent>,
+    open_seats: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct WinnerDiscrepancy {
+    riding: String,
+    district: u32,
+    computed: Party,
+    computed_votes: usize,
+    official: Option<Party>,
+    official_votes: Option<usize>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CandidateRun {
+    #[serde(flatten)]
+    id: RidingId,
+    riding: String,
+    party: Party,
+    name: String,
+    last_name: String,
+    first_name: String,
+    votes: usize,
+    ratio: f64,
+    won: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct HistogramBin {
+    from: f64,
+    to: f64,
+    ridings: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct LostDeposit {
+    riding: String,
+    district: u32,
+    party: Party,
+    name: String,
+    last_name: String,
+    first_name: String,
+    votes: usize,
+    ratio: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PartyDeposits {
+    party: Party,
+    candidates: usize,
+    lost: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Deposits {
+    threshold: f64,
+    candidates: usize,
+    lost: usize,
+    parties: Vec<PartyDeposits>,
+    lost_deposits: Vec<LostDeposit>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SeatFlip {
+    riding: String,
+    before: RidingId,
+    after: RidingId,
+    before_winner: Party,
+    before_margin: f64,
+    after_winner: Party,
+    after_margin: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Swing {
+    riding: String,
+    district: u32,
+    before: f64,
+    after: f64,
+    swing: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PartySwings {
+    party: Party,
+    toward: Vec<Swing>,
+    away: Vec<Swing>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CareerStats {
+    first_name: String,
+    name: String,
+    last_name: String,
+    runs: usize,
+    wins: usize,
+    longest_streak: usize,
+    losses_before_first_win: Option<usize>,
+    largest_swing: Option<f64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DecisiveVotes {
+    riding: String,
+    district: u32,
+    winner: Party,
+    runner_up: Party,
+    /// Voters switching from the winner to the runner-up.
+    votes: usize,
+    /// Alternatively, the winner's voters staying home.
+    abstentions: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct LeaderChange {
+    challenger: Party,
+    votes: usize,
+    ridings: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SeatChange {
+    votes: usize,
+    ridings: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct MajorityChange {
+    party: Party,
+    #[serde(flatten)]
+    change: SeatChange,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Decisive {
+    leader: Party,
+    magic_number: usize,
+    to_change_leader: Option<LeaderChange>,
+    /// Set when the leader won a majority.
+    to_deny_majority: Option<SeatChange>,
+    /// Set when nobody won a majority.
+    to_grant_majority: Option<MajorityChange>,
+    ridings: Vec<DecisiveVotes>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct HistoryEntry {
+    #[serde(flatten)]
+    id: RidingId,
+    riding: String,
+    winner: Party,
+    margin: f64,
+    turnout: Option<f64>,
+    shares: BTreeMap<Party, f64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ProfileCandidate {
+    rank: usize,
+    party: Party,
+    name: String,
+    last_name: String,
+    first_name: String,
+    votes: usize,
+    ratio: f64,
+    incumbent: bool,
+    elected: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RidingProfile {
+    #[serde(flatten)]
+    id: RidingId,
+    riding: String,
+    winner: Party,
+    margin: f64,
+    total_votes: usize,
+    rejected: usize,
+    electors: usize,
+    turnout: Option<f64>,
+    candidates: Vec<ProfileCandidate>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct QuebecRiding {
+    riding: String,
+    district: u32,
+    winner: Party,
+    bloc_votes: usize,
+    bloc_ratio: f64,
+    federalist_votes: usize,
+    federalist_ratio: f64,
+    non_bloc_votes: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Quebec {
+    bloc_seats: usize,
+    federalist_seats: usize,
+    bloc_ratio: f64,
+    federalist_ratio: f64,
+    /// Ridings the Bloc won despite more people voting against it than for it.
+    split_opposition: Vec<QuebecRiding>,
+    ridings: Vec<QuebecRiding>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SpecialShares {
+    special: f64,
+    in_person: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SpecialRiding {
+    riding: String,
+    district: u32,
+    special_votes: usize,
+    in_person_votes: usize,
+    in_person_leader: Party,
+    winner: Party,
+    shares: BTreeMap<Party, SpecialShares>,
+    /// Did the in-person leader lose? Not always a matter of parties, since
+    /// one independent can overtake another.
+    #[serde(skip)]
+    flipped: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct AdvanceShares {
+    advance: f64,
+    election_day: f64,
+    /// Advance share minus election-day share.
+    difference: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct AdvanceRiding {
+    riding: String,
+    district: u32,
+    advance_votes: usize,
+    election_day_votes: usize,
+    /// Half the sum of the absolute differences in share: the fraction of
+    /// advance voters who'd have to vote differently to match election day.
+    divergence: f64,
+    shares: BTreeMap<Party, AdvanceShares>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SpecialBallots {
+    /// Ridings where the in-person leader lost once special ballots were
+    /// counted.
+    flipped: Vec<String>,
+    ridings: Vec<SpecialRiding>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ClassSummary {
+    class: String,
+    ridings: usize,
+    parties: Vec<VoteCount>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct LateFlip {
+    riding: String,
+    district: u32,
+    election_day_leader: Party,
+    election_day_lead: usize,
+    winner: Party,
+    advance_votes: usize,
+    special_votes: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RidingCandidates {
+    riding: String,
+    district: u32,
+    candidates: usize,
+    registered: usize,
+    fringe: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CandidateFrequency {
+    candidates: usize,
+    ridings: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CandidateCounts {
+    ridings: Vec<RidingCandidates>,
+    distribution: Vec<CandidateFrequency>,
+    most: Vec<String>,
+    fewest: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Heartlands {
+    party: Party,
+    contested: usize,
+    strongest: Vec<PartyResults>,
+    weakest: Vec<PartyResults>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SeatBlock {
+    order: usize,
+    party: Party,
+    seats: usize,
+    colour: &'static str,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PollResult {
+    riding: String,
+    district: u32,
+    number: String,
+    station: String,
+    electors: usize,
+    rejected: usize,
+    party: Party,
+    name: String,
+    last_name: String,
+    first_name: String,
+    votes: usize,
+    ratio: f64,
+    /// Did this candidate lead this poll?
+    won: bool,
+}
+
+#[derive(Clone, Serialize, JsonSchema)]
+struct PollTurnout {
+    number: String,
+    station: String,
+    electors: usize,
+    turnout: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RidingTurnout {
+    riding: String,
+    district: u32,
+    polls: usize,
+    mean: f64,
+    variance: f64,
+    highest: PollTurnout,
+    lowest: PollTurnout,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RejectedBallots {
+    riding: String,
+    district: u32,
+    rejected: usize,
+    /// Rejected ballots as a share of all ballots cast.
+    rate: f64,
+    winner: Party,
+    /// The winner's lead over the runner-up, in votes.
+    margin: usize,
+    /// Were there more rejected ballots than the margin of victory?
+    exceeds_margin: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Competitiveness {
+    province: Province,
+    seats: usize,
+    average_margin: f64,
+    /// Seats won by less than [`CLOSE_MARGIN`].
+    close_seats: usize,
+    close_ratio: f64,
+    /// The mean of `close_ratio` and `1 - average_margin`. Higher is more
+    /// contested.
+    index: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Fragmentation {
+    riding: String,
+    district: u32,
+    winner: Party,
+    margin: f64,
+    /// The Herfindahl-Hirschman index of vote shares.
+    hhi: f64,
+    /// How many equally strong candidates would give the same concentration.
+    effective_candidates: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct IndependentResult {
+    riding: String,
+    district: u32,
+    party: Party,
+    name: String,
+    last_name: String,
+    first_name: String,
+    votes: usize,
+    ratio: f64,
+    /// Where the candidate finished, 1 being the winner.
+    placing: usize,
+    won: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Independents {
+    candidates: usize,
+    ridings: usize,
+    won: usize,
+    results: Vec<IndependentResult>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Spoiler {
+    riding: String,
+    district: u32,
+    party: Party,
+    votes: usize,
+    winner: Party,
+    winner_votes: usize,
+    runner_up: Party,
+    runner_up_votes: usize,
+    /// The winner's lead over the runner-up, in votes.
+    margin: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Coverage {
+    party: Party,
+    contested: usize,
+    /// The share of all ridings that the party contested.
+    coverage: f64,
+    /// The party's average share of the vote in the ridings it contested.
+    average_ratio: f64,
+    skipped: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PartyFingerprint {
+    party: Party,
+    national: f64,
+    /// Provincial share over national share, in the order of
+    /// [`Fingerprint::provinces`]. Above 1 means the party overperformed there.
+    relative: Vec<f64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Fingerprint {
+    provinces: Vec<Province>,
+    parties: Vec<PartyFingerprint>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CaucusMargins {
+    party: Party,
+    seats: usize,
+    average: f64,
+    median: f64,
+    /// The narrowest of the party's wins.
+    closest: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Inversion {
+    year: usize,
+    vote_winner: Party,
+    vote_winner_ratio: f64,
+    vote_winner_seats: usize,
+    seat_winner: Party,
+    seat_winner_ratio: f64,
+    seat_winner_seats: usize,
+    /// Did the party with the most votes fail to win the most seats?
+    inverted: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ByElection {
+    date: String,
+    /// The general election whose seat was being refilled.
+    general: Option<usize>,
+    riding: String,
+    district: u32,
+    winner: Party,
+    margin: f64,
+    previous_winner: Option<Party>,
+    flipped: bool,
+    candidates: Vec<PartyResults>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SeatTimeline {
+    general: usize,
+    byelections: usize,
+    /// Net seats gained or lost by each party through by-elections.
+    changes: BTreeMap<Party, isize>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ByElections {
+    byelections: Vec<ByElection>,
+    timeline: Vec<SeatTimeline>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PartyMajorities {
+    party: Party,
+    seats: usize,
+    majority: usize,
+    plurality: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Majorities {
+    seats: usize,
+    plurality: usize,
+    /// The share of all MPs elected without majority support.
+    plurality_ratio: f64,
+    parties: Vec<PartyMajorities>,
+}
+
+fn main() -> Result<(), Error> {
+    let config = Config::load()?;
+    let argv = expand_preset(&config)?;
+
+    // The config file's defaults only apply to the flags left unset, both on
+    // the command line and in the environment.
+    let defaults = config.defaults.flags();
+    let app = defaults
+        .iter()
+        .fold(Args::into_app(), |app, (flag, value)| {
+            app.mut_arg(*flag, |a| a.default_value(value))
+        });
+    let args = Args::from_arg_matches(&app.get_matches_from(argv)).expect("parsed by clap");
+    logging(&args);
+
+    // Colours are only for the terminal, never for files and the like.
+    let elsewhere =
+        args.output.is_some() || !matches!(args.sink.as_deref(), None | Some("stdout") | Some("-"));
+    let settings = Settings {
+        jurisdiction: args.jurisdiction,
+        data_dir: args.data_dir.clone(),
+        no_cache: args.no_cache || std::env::var_os("ELECTION_NO_CACHE").is_some(),
+        corrections: args.corrections.clone(),
+        redistribution: args.redistribution.clone(),
+        colour: !(args.no_color || config.defaults.no_color || elsewhere) && format::coloured(),
+    };
+
+    // Built-in analyses take precedence over plugins of the same name, since
+    // clap only leaves the names it doesn't know.
+    if let Command::Plugin(argv) = &args.command {
+        let name = &argv[0];
+        let plugin = plugins::find(name).ok_or_else(|| {
+            let msg = format!("Unknown command, and no election-{} on the PATH", name);
+            Error::new(ErrorKind::NotFound, msg)
+        })?;
+        return plugins::dispatch(&plugin, &argv[1..], &settings);
+    }
+
+    if args.watch {
+        watch(&args, &settings)
+    } else if args.live {
+        live(&args, &settings)
+    } else {
+        run(&args, &settings, &config, None)
+    }
+}
+
+/// Send logs to stderr: warnings by default, then more with each `-v`.
+fn logging(args: &Args) {
+    let level = match args.verbose {
+        _ if args.quiet => Level::ERROR,
+        0 => Level::WARN,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_ansi(
+            !args.no_color
+                && std::env::var_os("NO_COLOR").is_none()
+                && std::io::stderr().is_terminal(),
+        )
+        .without_time()
+        .init();
+}
+
+/// Load the data for the requested year and run the chosen analysis. Polls
+/// already in hand, like those of the live feed, are used instead if given.
+fn run(
+    args: &Args,
+    settings: &Settings,
+    config: &Config,
+    polls: Option<Vec<Poll>>,
+) -> Result<(), Error> {
+    // Read up front, so that the envelope can say what was read.
+    let polls = match polls {
+        None if args.stdin => Some(read_stdin()?),
+        polls => polls,
+    };
+    let sink = match &args.output {
+        Some(path) => Some(format!("file://{}", path.display())),
+        None => args.sink.clone(),
+    };
+    let format = match args.format {
+        Some(format) => format,
+        None if args.json => Format::Json,
+        None => match args.output.as_deref().and_then(Format::from_extension) {
+            Some(format) => format,
+            None if args.envelope => Format::Json,
+            None => match &config.defaults.format {
+                Some(name) => Format::from_str(name, true).map_err(|_| {
+                    let msg = format!("Unknown format in the config file: {}", name);
+                    Error::new(ErrorKind::InvalidData, msg)
+                })?,
+                None => Format::detect(sink.as_deref()),
+            },
+        },
+    };
+    let mut out = Output::new(sink.as_deref())?
+        .sort_by(args.sort_by.clone(), args.desc)
+        .top(args.top)
+        .riding_filter(args.riding_filter.clone())
+        .regexes(args.riding_regex.clone(), args.candidate_regex.clone())
+        .tidy(args.tidy)
+        .colour(settings.colour)
+        .format(format);
+
+    if args.envelope {
+        if !matches!(format, Format::Json | Format::Yaml) {
+            let msg = "--envelope needs JSON or YAML output";
+            return Err(Error::new(ErrorKind::InvalidInput, msg));
+        }
+        let digest = data_digest(args, settings, polls.as_deref())?;
+        out = out.envelope(Envelope::new(args.year, digest));
+    }
+    if args.lang == Lang::Fr {
+        out = out.translate(Translation::french(settings, args.year));
+    }
+    let aliases = Aliases::new(&config.aliases);
+
+    if args.cache {
+        let key = format!(
+            "{}\n{:?}\n{:?}\n{:?}\n{:?}",
+            crate_version!(),
+            args,
+            format,
+            data_files(settings)?,
+            input_files(args, settings)
+        );
+
+        if let Some(path) = cache::path(&key) {
+            match std::fs::read_to_string(&path) {
+                Ok(body) => return out.write(&body),
+                Err(_) => out = out.cached(&path),
+            }
+        }
+    }
+
+    let classes = args.classes.as_deref().map(Classes::load).transpose()?;
+    let small_polls = args.small_polls.map(|mode| SmallPolls {
+        mode,
+        size: args.small_poll_size,
+    });
+
+    match &args.command {
+        // These span every year, so there is no single year to load.
+        Command::Candidate { name } => candidate_search(settings, name, &mut out),
+        Command::Careers => career_tracker(settings, &aliases, &mut out),
+        Command::Switchers => party_switchers(settings, &aliases, &mut out),
+        Command::Streaks => career_streaks(settings, &aliases, &mut out),
+        Command::Compare { from, to } => seat_flips(settings, *from, *to, &aliases, &mut out),
+        Command::Swing { from, to } => swings(settings, *from, *to, &aliases, &mut out),
+        Command::History { riding } => riding_history(settings, riding, &aliases, &mut out),
+        Command::Inversions => inversions(settings, &mut out),
+        Command::Byelections => byelections(settings, &mut out),
+        // These look after the data itself.
+        Command::Fetch(fetch) => out.emit(&fetch::fetch(settings, fetch)?),
+        Command::Validate(validate) => out.emit(&validate::validate(settings, validate)?),
+        // Schemas aren't tabular, so they're always JSON.
+        Command::Schema { name } => {
+            Output::new(sink.as_deref())?.emit(&schema::schema(name.as_deref())?)
+        }
+        Command::Sample { dir, ridings, seed } => {
+            let from = data_dir(settings, args.year);
+            let sample = sample::extract(&from, dir, args.year, *ridings, *seed)?;
+            out.emit(&sample)
+        }
+        // These look at each poll on its own.
+        Command::ByPoll => {
+            let mut polls = selected(args, settings, polls, &classes)?;
+            if let Some(small) = small_polls {
+                polls = small.apply(polls);
+            }
+            poll_results(polls, &mut out)
+        }
+        Command::PollTurnout => poll_turnout(selected(args, settings, polls, &classes)?, &mut out),
+        Command::Export(export) => {
+            let polls = selected(args, settings, polls, &classes)?;
+            out.emit(&export::export(
+                settings,
+                export,
+                args.year,
+                polls,
+                small_polls,
+            )?)
+        }
+        command => {
+            let unified = corrected(
+                settings,
+                args.year,
+                fuse(selected(args, settings, polls, &classes)?),
+            )?;
+
+            match command {
+                Command::Totals => totals(unified, &mut out),
+                Command::Combine { parties } => combined(unified, parties, &mut out),
+                Command::Margins { absolute } => victory_margins(unified, *absolute, &mut out),
+                Command::Party { parties } => party_results(unified, parties, &mut out),
+                Command::Candidates => candidate_counts(unified, &mut out),
+                Command::Incumbents => incumbency(unified, args.year, &mut out),
+                Command::Verify => verify_winners(unified, &mut out),
+                Command::Histogram { party, bin_width } => {
+                    share_histogram(unified, party, *bin_width, &mut out)
+                }
+                Command::Riding { riding } => {
+                    riding_profile(unified, riding, args.year, &aliases, &mut out)
+                }
+                Command::Quebec => quebec(unified, &mut out),
+                Command::Advance => advance_voting(unified, &mut out),
+                Command::Special => special_ballots(unified, &mut out),
+                Command::ByClass => match &classes {
+                    Some(classes) => class_breakdown(unified, classes, &mut out),
+                    None => {
+                        let msg = "by-class needs --classes FILE";
+                        Err(Error::new(ErrorKind::InvalidInput, msg))
+                    }
+                },
+                Command::LateFlips => late_flips(unified, &mut out),
+                Command::Decisive => decisive_votes(unified, &mut out),
+                Command::Deposits { threshold } => lost_deposits(unified, *threshold, &mut out),
+                Command::Heartlands { party, count } => {
+                    heartlands(unified, party, *count, &mut out)
+                }
+                Command::Rejected => rejected_ballots(unified, &mut out),
+                Command::Competitiveness => competitiveness(unified, &mut out),
+                Command::Fragmentation => fragmentation(unified, &mut out),
+                Command::Independents => independents(unified, &mut out),
+                Command::Spoilers { party } => spoilers(unified, party, &mut out),
+                Command::Coverage => coverage(unified, &mut out),
+                Command::Fingerprint => fingerprint(unified, &mut out),
+                Command::CaucusMargins => caucus_margins(unified, &mut out),
+                Command::Hemicycle => hemicycle(unified, &mut out),
+                Command::Majorities => majorities(unified, &mut out),
+                Command::Chart(chart) => {
+                    let file = args.output.as_deref();
+                    chart::chart(settings, chart, args.year, unified, file, &mut out)
+                }
+                Command::Report => report::report(args.year, unified, &mut out),
+                _ => unreachable!("handled above"),
+            }
+        }
+    }
+}
+
+/// The year's polls, or those given, less any of the classes or kinds of
+/// poll left out.
+fn selected(
+    args: &Args,
+    settings: &Settings,
+    polls: Option<Vec<Poll>>,
+    classes: &Option<Classes>,
+) -> Result<Vec<Poll>, Error> {
+    let mut polls = match polls {
+        Some(polls) => prepare(polls),
+        None => load_polls(settings, args.year)?,
+    };
+
+    let before = polls.len();
+
+    if let (Some(classes), Some(class)) = (classes, &args.class) {
+        polls.retain(|p| classes.of(p.district, &p.riding) == Some(class.as_str()));
+    }
+
+    polls.retain(|p| !args.exclude_polls.contains(&p.kind()));
+    let polls = without_parties(polls, &args.exclude_party)?;
+    debug!(
+        kept = polls.len(),
+        skipped = before - polls.len(),
+        "filtered"
+    );
+
+    Ok(polls)
+}
+
+/// Leave out the candidates of the given parties. A riding left with nobody
+/// in it can't be won by anyone, and quietly dropping it would shrink the
+/// House, so that's an error instead.
+fn without_parties(polls: Vec<Poll>, parties: &[Party]) -> Result<Vec<Poll>, Error> {
+    if parties.is_empty() {
+        return Ok(polls);
+    }
+
+    let ridings: Vec<String> = polls.iter().map(|p| p.riding.clone()).unique().collect();
+    let kept: Vec<Poll> = polls
+        .into_iter()
+        .filter(|p| !parties.contains(&p.party))
+        .collect();
+    let empty: Vec<String> = ridings
+        .into_iter()
+        .filter(|r| !kept.iter().any(|p| &p.riding == r))
+        .collect();
+
+    if empty.is_empty() {
+        Ok(kept)
+    } else {
+        let msg = format!("No candidates would be left in: {}", empty.join(", "));
+        Err(Error::new(ErrorKind::InvalidInput, msg))
+    }
+}
+
+/// Read every poll of the given year, fused into a single entry per candidate.
+fn load(settings: &Settings, year: usize) -> Result<Vec<Poll>, Error> {
+    corrected(settings, year, fuse(load_polls(settings, year)?))
+}
+
+/// Apply any corrections given for the year to its fused polls.
+fn corrected(settings: &Settings, year: usize, mut polls: Vec<Poll>) -> Result<Vec<Poll>, Error> {
+    if let Some(path) = &settings.corrections {
+        Corrections::load(path)?.apply(year, &mut polls)?;
+    }
+
+    Ok(polls)
+}
+
+/// Read every poll of the given year as-is: an entry per candidate per poll.
+/// Parsing is skipped in favour of the binary copy kept from an earlier run,
+/// so long as the year's files haven't changed since.
+fn load_polls(settings: &Settings, year: usize) -> Result<Vec<Poll>, Error> {
+    let jurisdiction = settings.jurisdiction.get();
+    if jurisdiction.years().contains(&year).not() {
+        let msg = format!(
+            "No {} general election was held in {}",
+            jurisdiction.name(),
+            year
+        );
+        return Err(Error::new(ErrorKind::NotFound, msg));
+    }
+    if settings.no_cache {
+        return read_polls(&data_dir(settings, year));
+    }
+
+    let stamp = format!("{}\n{:?}", crate_version!(), year_files(settings, year)?);
+
+    match cache::read_polls(year, &stamp) {
+        Some(polls) => {
+            info!(year, rows = polls.len(), "read the cached copy of the data");
+            Ok(polls)
+        }
+        None => {
+            let polls = read_polls(&data_dir(settings, year))?;
+            // Not being able to keep a copy is no reason to fail the query.
+            if let Err(e) = cache::write_polls(year, &stamp, &polls) {
+                warn!(year, error = %e, "couldn't cache the data");
+            }
+            Ok(polls)
+        }
+    }
+}
+
+/// Read every poll in the files of the given directory. The CSVs can also be
+/// left inside the `.zip` archives that Elections Canada distributes them in,
+/// and riding-level summary tables can stand in for poll-by-poll files.
+fn read_polls(dir: &Path) -> Result<Vec<Poll>, Error> {
+    let mut polls = Vec::new();
+    let paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|de| de.ok())
+        .map(|de| de.path())
+        .collect();
+    let bar = progress(paths.len());
+
+    for path in paths {
+        if path.extension().is_some_and(|e| e == "zip") {
+            let mut zip = zip::ZipArchive::new(std::fs::File::open(&path)?)?;
+            // Each of the archive's files counts, rather than the archive.
+            bar.inc_length(zip.len().saturating_sub(1) as u64);
+
+            for i in 0..zip.len() {
+                let entry = zip.by_index(i)?;
+
+                if entry.name().ends_with(".csv") {
+                    let name = format!("{}/{}", path.display(), entry.name());
+                    let read = read_csv(csv::Reader::from_reader(entry))?;
+                    info!(file = %name, rows = read.len(), "read");
+                    bar.set_message(format!("{} ({} rows)", name, read.len()));
+                    polls.extend(read);
+                } else {
+                    debug!(file = %path.display(), entry = entry.name(), "skipped, not a CSV");
+                }
+                bar.inc(1);
+            }
+        } else if let Ok(reader) = csv::Reader::from_path(&path) {
+            let read = read_csv(reader)?;
+            info!(file = %path.display(), rows = read.len(), "read");
+            bar.set_message(format!("{} ({} rows)", path.display(), read.len()));
+            polls.extend(read);
+            bar.inc(1);
+        } else {
+            debug!(file = %path.display(), "skipped, unreadable");
+            bar.inc(1);
+        }
+    }
+
+    bar.finish_and_clear();
+    Ok(prepare(polls))
+}
+
+/// A progress bar over the given number of files, drawn on stderr. It's only
+/// shown on a terminal, and not alongside logs of the same files or when
+/// asked to be quiet.
+fn progress(files: usize) -> ProgressBar {
+    let level = LevelFilter::current();
+
+    if level == LevelFilter::ERROR || level > LevelFilter::WARN {
+        return ProgressBar::hidden();
+    }
+
+    let style = ProgressStyle::with_template("{bar:40} {pos}/{len} files  {wide_msg}")
+        .expect("a valid template");
+
+    ProgressBar::new(files as u64).with_style(style)
+}
+
+/// Read polls piped in on stdin, as one CSV file or several concatenated.
+fn read_stdin() -> Result<Vec<Poll>, Error> {
+    let stdin = std::io::stdin();
+    let polls = read_csv(csv::Reader::from_reader(stdin.lock()))?;
+    info!(rows = polls.len(), "read stdin");
+
+    Ok(prepare(polls))
+}
+
+/// Read the polls of one CSV. Repeated header rows, as found when several
+/// files are concatenated, are skipped.
+fn read_csv<R: std::io::Read>(mut reader: csv::Reader<R>) -> Result<Vec<Poll>, Error> {
+    let headers = reader.headers()?.clone();
+
+    if summary::is_summary(&headers) {
+        return summary::read(reader);
+    } else if transposed::is_transposed(&headers) {
+        return transposed::read(reader);
+    } else if let Some(jurisdiction) = jurisdiction::provincial(&headers) {
+        return jurisdiction::read(jurisdiction, reader);
+    }
+
+    let mut repeats = 0;
+    let polls = reader
+        .records()
+        .filter(|r| {
+            let repeat = r.as_ref().is_ok_and(|r| *r == headers);
+            repeats += repeat as usize;
+            !repeat
+        })
+        .map(|r| {
+            let poll: Poll = r?.deserialize(Some(&headers))?;
+            Ok(poll.classify())
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    if repeats > 0 {
+        debug!(rows = repeats, "skipped repeated headers");
+    }
+
+    Ok(polls)
+}
+
+/// Drop the entries that never held any votes, tidy up candidates' names, and
+/// sort the rest by riding, then party.
+fn prepare(mut polls: Vec<Poll>) -> Vec<Poll> {
+    // Neither kind of poll had any ballots cast, and their entries are only
+    // there for completeness.
+    let before = polls.len();
+    polls.retain(|p| !p.void && !p.not_held);
+    info!(
+        kept = polls.len(),
+        skipped = before - polls.len(),
+        "dropped void and unheld polls"
+    );
+    let mut polls: Vec<_> = polls.into_iter().map(Poll::tidy_names).collect();
+    polls.sort();
+    polls
+}
+
+/// Fuse each candidate's polls into one entry. Expects the polls to be sorted.
+fn fuse(polls: Vec<Poll>) -> Vec<Poll> {
+    polls
+        .into_iter()
+        // Several independents can run in the same riding, so it takes more
+        // than the party to tell candidates apart.
+        .group_by(|poll| {
+            (
+                poll.district,
+                poll.party.clone(),
+                poll.last_name.clone(),
+                poll.first_name.clone(),
+            )
+        })
+        .into_iter()
+        .filter_map(|(_, group)| group.reduce(|a, b| a.fuse(b)))
+        .collect()
+}
+
+/// Every supported year for which data is actually present.
+fn available_years(settings: &Settings) -> impl Iterator<Item = usize> + '_ {
+    settings
+        .jurisdiction
+        .get()
+        .years()
+        .iter()
+        .copied()
+        .filter(move |y| data_dir(settings, *y).is_dir())
+}
+
+/// Where all the data lives: the directory given by `--data-dir` or its
+/// stand-ins if any, else `./data` if there is one, else `election` within
+/// `XDG_DATA_HOME`.
+fn data_root(settings: &Settings) -> PathBuf {
+    settings
+        .data_dir
+        .clone()
+        .or_else(|| Some(PathBuf::from("data")).filter(|d| d.is_dir()))
+        .or_else(|| {
+            std::env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("share"))
+                })
+                .map(|d| d.join("election"))
+        })
+        .unwrap_or_else(|| PathBuf::from("data"))
+}
+
+/// Where the data for a given year lives.
+fn data_dir(settings: &Settings, year: usize) -> PathBuf {
+    settings.jurisdiction.get().dir(&data_root(settings), year)
+}
+
+/// Where by-election results live, one `YYYY-MM-DD` directory per polling day.
+fn byelection_dir(settings: &Settings) -> PathBuf {
+    data_root(settings).join("byelections")
+}
+
+/// Where the given year's results, transposed onto the boundaries of the next
+/// representation order, live.
+fn transposed_dir(settings: &Settings, year: usize) -> PathBuf {
+    data_root(settings)
+        .join("transposed")
+        .join(year.to_string())
+}
+
+/// The polling day of each supported general election.
+fn election_day(year: usize) -> Option<&'static str> {
+    match year {
+        2004 => Some("2004-06-28"),
+        2006 => Some("2006-01-23"),
+        2008 => Some("2008-10-14"),
+        2011 => Some("2011-05-02"),
+        2015 => Some("2015-10-19"),
+        2019 => Some("2019-10-21"),
+        2021 => Some("2021-09-20"),
+        _ => None,
+    }
+}
+
+/// Run the analysis, then run it again every time the data or the config
+/// file changes. Errors from individual runs are reported but don't stop the
+/// watching, since files are often caught halfway through being written.
+fn watch(args: &Args, settings: &Settings) -> Result<(), Error> {
+    let mut last = None;
+    let mut fired = HashMap::new();
+
+    loop {
+        let current = modification_times(settings, args.year)?;
+
+        if last.as_ref() != Some(&current) {
+            let result = Config::load().and_then(|config| {
+                run(args, settings, &config, None)?;
+
+                if config.alerts.is_empty() {
+                    Ok(())
+                } else {
+                    let ridings = ridings(load(settings, args.year)?);
+                    alerts::check(&config.alerts, args.year, &ridings, &mut fired)
+                }
+            });
+
+            if let Err(e) = result {
+                eprintln!("{}", e);
+            }
+            last = Some(current);
+        }
+
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Poll the live results feed, and rerun the analysis on it whenever it has
+/// changed. A failed download is reported and retried on the next round, since
+/// the feed is under heavy load on election night.
+fn live(args: &Args, settings: &Settings) -> Result<(), Error> {
+    let mut last = None;
+    let mut fired = HashMap::new();
+
+    loop {
+        let result = live::download(&args.feed).and_then(|body| {
+            if last.as_ref() == Some(&body) {
+                return Ok(());
+            }
+
+            let polls = live::read(&body)?;
+            let config = Config::load()?;
+            run(args, settings, &config, Some(polls.clone()))?;
+
+            if config.alerts.is_empty().not() {
+                let ridings = ridings(fuse(prepare(polls)));
+                alerts::check(&config.alerts, args.year, &ridings, &mut fired)?;
+            }
+
+            last = Some(body);
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+
+        std::thread::sleep(Duration::from_secs(LIVE_INTERVAL));
+    }
+}
+
+/// The size and last-modified time of every data file of every year, which
+/// together stand in for the state of the dataset.
+fn data_files(settings: &Settings) -> Result<Vec<(PathBuf, u64, SystemTime)>, Error> {
+    let mut files = Vec::new();
+
+    for year in available_years(settings) {
+        files.extend(year_files(settings, year)?);
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// The size and last-modified time of everything besides the years' data
+/// that an analysis can read: the files given with --classes, --corrections
+/// and --redistribution, the config file and its aliases, and the by-election
+/// and transposed results.
+fn input_files(args: &Args, settings: &Settings) -> Vec<(PathBuf, u64, SystemTime)> {
+    let listing = |dir: PathBuf| -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .map(|rd| rd.filter_map(|de| de.ok()).map(|de| de.path()).collect())
+            .unwrap_or_default()
+    };
+    // Both keep a directory of files per polling day or year.
+    let nested = vec![
+        byelection_dir(settings),
+        data_root(settings).join("transposed"),
+    ]
+    .into_iter()
+    .flat_map(listing)
+    .flat_map(listing);
+    let named = vec![
+        args.classes.clone(),
+        settings.corrections.clone(),
+        settings.redistribution.clone(),
+        config::path(),
+    ];
+
+    named
+        .into_iter()
+        .flatten()
+        .chain(nested)
+        .filter_map(|path| {
+            let meta = std::fs::metadata(&path).ok()?;
+            Some((path, meta.len(), meta.modified().ok()?))
+        })
+        .sorted()
+        .collect()
+}
+
+/// A SHA-256 digest of everything the command reads, to tell later whether
+/// results were computed from the same data: the polls in hand, or else the
+/// files of every directory of results it draws on, along with the files
+/// given with --classes, --corrections and --redistribution and the config
+/// file with its aliases.
+fn data_digest(args: &Args, settings: &Settings, polls: Option<&[Poll]>) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    let mut paths = Vec::new();
+
+    match (dirs_read(args, settings), polls) {
+        (None, Some(polls)) => hasher.update(format!("{:?}", polls)),
+        (dirs, _) => {
+            let dirs = dirs.unwrap_or_else(|| vec![data_dir(settings, args.year)]);
+            for dir in dirs {
+                let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+                    .filter_map(|de| de.ok())
+                    .map(|de| de.path())
+                    .collect();
+                files.sort();
+                paths.extend(files);
+            }
+        }
+    }
+
+    // Only comparisons across a redistribution read the transposition.
+    let pairs = matches!(
+        args.command,
+        Command::Compare { .. } | Command::Swing { .. }
+    );
+    let named = vec![
+        args.classes.clone(),
+        settings.corrections.clone(),
+        settings.redistribution.clone().filter(|_| pairs),
+        config::path(),
+    ];
+
+    for path in paths
+        .into_iter()
+        .chain(named.into_iter().flatten())
+        .filter(|path| path.is_file())
+    {
+        hasher.update(path.file_name().unwrap_or_default().as_encoded_bytes());
+        std::io::copy(&mut std::fs::File::open(&path)?, &mut hasher)?;
+    }
+
+    Ok(output::hex(&hasher.finalize()))
+}
+
+/// The directories of results the command reads for itself: every year's for
+/// those that span them, and none for those that don't read results at all.
+/// `None` for the rest, which read the year's polls or those in hand.
+fn dirs_read(args: &Args, settings: &Settings) -> Option<Vec<PathBuf>> {
+    let years = |years: Vec<usize>| -> Vec<PathBuf> {
+        years.into_iter().map(|y| data_dir(settings, y)).collect()
+    };
+    let every = || years(available_years(settings).collect());
+
+    match &args.command {
+        Command::Candidate { .. }
+        | Command::Careers
+        | Command::Switchers
+        | Command::Streaks
+        | Command::History { .. }
+        | Command::Inversions => Some(every()),
+        Command::Byelections => {
+            let days: Vec<PathBuf> = std::fs::read_dir(byelection_dir(settings))
+                .map(|rd| rd.filter_map(|de| de.ok()).map(|de| de.path()).collect())
+                .unwrap_or_default();
+            Some(every().into_iter().chain(days).sorted().collect())
+        }
+        Command::Compare { from, to } | Command::Swing { from, to } => {
+            let transposed = transposed_dir(settings, *from);
+            let mut dirs = years(vec![*from, *to]);
+            dirs.extend(Some(transposed).filter(|d| d.is_dir()));
+            Some(dirs)
+        }
+        Command::Chart(chart) => chart.years(args.year, available_years(settings)).map(years),
+        Command::Sample { .. } => Some(years(vec![args.year])),
+        Command::Validate(validate) => Some(vec![validate.dir(settings)]),
+        Command::Fetch(_) | Command::Schema { .. } => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+/// The size and last-modified time of every data file of the given year.
+fn year_files(settings: &Settings, year: usize) -> Result<Vec<(PathBuf, u64, SystemTime)>, Error> {
+    let mut files = Vec::new();
+
+    for de in std::fs::read_dir(data_dir(settings, year))?.filter_map(|de| de.ok()) {
+        let meta = de.metadata()?;
+        files.push((de.path(), meta.len(), meta.modified()?));
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// The last-modified times of every file that an analysis depends on.
+fn modification_times(
+    settings: &Settings,
+    year: usize,
+) -> Result<Vec<(PathBuf, SystemTime)>, Error> {
+    let mut times: Vec<_> = std::fs::read_dir(data_dir(settings, year))?
+        .filter_map(|de| de.ok())
+        .map(|de| de.path())
+        .chain(config::path())
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect();
+
+    times.sort();
+
+    Ok(times)
+}
+
+/// Pair up the ridings of two elections. Ridings are matched by district
+/// number when both elections used the same boundaries. Otherwise, the earlier
+/// results are restated on the later boundaries, preferring Elections Canada's
+/// own transposition to one given with `--redistribution`, and ridings are
+/// matched by name (or any known alias) if neither is available.
+/// Ridings without a counterpart are dropped.
+fn pair_ridings(
+    settings: &Settings,
+    before: Vec<Riding>,
+    before_year: usize,
+    after: Vec<Riding>,
+    after_year: usize,
+    aliases: &Aliases,
+) -> Result<Vec<(Riding, Riding)>, Error> {
+    let order = |year| RidingId { district: 0, year }.representation_order();
+
+    if order(before_year) != order(after_year) {
+        let official = transposed_dir(settings, before_year);
+        let transposed = if official.is_dir() {
+            Some(ridings(fuse(read_polls(&official)?)))
+        } else {
+            match &settings.redistribution {
+                Some(path) => Some(Redistribution::load(path)?.transpose(&before, &after)),
+                None => None,
+            }
+        };
+
+        if let Some(before) = transposed {
+            let mut after: HashMap<u32, Riding> =
+                after.into_iter().map(|a| (a.district, a)).collect();
+
+            return Ok(before
+                .into_iter()
+                .filter_map(|b| after.remove(&b.district).map(|a| (b, a)))
+                .collect());
+        }
+    }
+
+    let mut after: Vec<Option<Riding>> = after.into_iter().map(Some).collect();
+
+    Ok(before
+        .into_iter()
+        .filter_map(|b| {
+            let b_id = RidingId {
+                district: b.district,
+                year: before_year,
+            };
+            let i = after.iter().position(|a| {
+                a.as_ref().is_some_and(|a| {
+                    let a_id = RidingId {
+                        district: a.district,
+                        year: after_year,
+                    };
+                    a_id.corresponds_to(&b_id) || aliases.same(&a.name, &b.name)
+                })
+            })?;
+
+            after[i].take().map(|a| (b, a))
+        })
+        .collect())
+}
+
+/// Expand `election run <PRESET> [ARGS]` into the arguments that the preset
+/// stands for. Any extra arguments are appended to those of the preset.
+fn expand_preset(config: &Config) -> Result<Vec<String>, Error> {
+    let mut argv: Vec<String> = std::env::args().collect();
+
+    if argv.get(1).map(|a| a.as_str()) == Some("run") {
+        let name = argv.get(2).ok_or_else(|| {
+            let msg = format!("Available presets: {}", config.preset_names().join(", "));
+            Error::new(ErrorKind::InvalidInput, msg)
+        })?;
+        let preset = config.preset(name).ok_or_else(|| {
+            let msg = format!("Unknown preset: {}", name);
+            Error::new(ErrorKind::NotFound, msg)
+        })?;
+        let rest = argv.split_off(3);
+        argv.truncate(1);
+        argv.extend(preset);
+        argv.extend(rest);
+    }
+
+    Ok(argv)
+}
+
+fn ridings(polls: Vec<Poll>) -> Vec<Riding> {
+    polls
+        .into_iter()
+        .group_by(|poll| (poll.district, poll.riding.clone()))
+        .into_iter()
+        .map(|((district, name), group)| {
+            let polls: Vec<Poll> = group.collect();
+            // Each candidate's entry covers the same polls, so any will do.
+            let electors = polls.first().map_or(0, |p| p.electors);
+            let rejected = polls.first().map_or(0, |p| p.rejected);
+            let mut candidates: HashMap<Party, Candidate> = HashMap::new();
+            let mut unaffiliated = Vec::new();
+
+            for poll in polls {
+                let p = poll.party;
+                let c = Candidate {
+                    last_name: poll.last_name,
+                    first_name: poll.first_name,
+                    middle_name: poll.middle_name,
+                    votes: poll.votes,
+                    ballots: poll.ballots,
+                    incumbent: poll.incumbent,
+                    elected: poll.elected,
+                };
+
+                if p.is_unaffiliated() {
+                    unaffiliated.push((p.clone(), c.clone()));
+                }
+
+                let c = match candidates.remove(&p) {
+                    Some(other) => other.fuse(c),
+                    None => c,
+                };
+                candidates.insert(p, c);
+            }
+
+            Riding {
+                name,
+                district,
+                electors,
+                rejected,
+                candidates,
+                unaffiliated,
+            }
+        })
+        .collect()
+}
+
+/// How a given [`Party`] did in every riding.
+fn party_results(polls: Vec<Poll>, parties: &[Party], out: &mut Output) -> Result<(), Error> {
+    let results: Vec<PartyResults> = parties
+        .iter()
+        .unique()
+        .flat_map(|party| results_of(polls.clone(), party))
+        .collect();
+
+    out.emit(&results)
+}
+
+/// A [`Party`]'s results in every riding it contested, from its weakest to
+/// its strongest.
+fn results_of(polls: Vec<Poll>, party: &Party) -> Vec<PartyResults> {
+    let mut results: Vec<_> = ridings(polls)
+        .into_iter()
+        .filter_map(|mut riding| {
+            let won = riding.was_winner(party);
+            riding.candidates.remove(party).map(|c| (riding, won, c))
+        })
+        .map(|(riding, won, c)| {
+            //
+            let total = riding.total_votes() + c.votes;
+            let ratio = c.votes as f64 / total as f64;
+
+            PartyResults {
+                riding: riding.name,
+                district: riding.district,
+                party: party.clone(),
+                name: c.name(),
+                last_name: c.last_name,
+                first_name: c.first_name,
+                votes: c.votes,
+                ratio,
+                won,
+            }
+        })
+        .collect();
+
+    results.sort_by(|a, b| a.ratio.total_cmp(&b.ratio));
+    results
+}
+
+/// Ordered list of ridings by the victory margin, either as a share of the
+/// vote or in raw votes.
+fn victory_margins(polls: Vec<Poll>, absolute: bool, out: &mut Output) -> Result<(), Error> {
+    out.emit(&margins_of(polls, absolute))
+}
+
+fn margins_of(polls: Vec<Poll>, absolute: bool) -> Vec<VictoryMargin> {
+    let mut margins: Vec<_> = ridings(polls)
+        .into_iter()
+        .map(|riding| {
+            let margin = riding.victory_margin();
+            let votes = riding.vote_margin();
+            let winner = riding.winner();
+
+            VictoryMargin {
+                winner: winner.clone(),
+                riding: riding.name,
+                district: riding.district,
+                margin,
+                votes,
+            }
+        })
+        .collect();
+
+    if absolute {
+        margins.sort_by_key(|m| m.votes);
+    } else {
+        margins.sort_by(|a, b| a.margin.total_cmp(&b.margin));
+    }
+
+    margins
+}
+
+/// For ridings in which none of the given parties won, would their combined
+/// votes have swung the result? The classic case is CON + PPC.
+///
+/// False Assumption #1: All PPC voters are naturally right-wing and would have
+/// otherwise voted CON. Similar to Trump voters in the USA, a section of the
+/// voter base are those disenfranchised with the existing parties and who just
+/// want a new alternative. While right-wing in nature, surely the PPC are
+/// drawing voters from all parts of Canada.
+///
+/// False Assumption #2: Everyone has a fixed party loyalty, and nobody ever
+/// votes for other reasons. In reality there are a myriad of reasons why people
+/// choose a particular party to vote for in a particular riding in a particular
+/// year.
+fn combined(polls: Vec<Poll>, parties: &[Party], out: &mut Output) -> Result<(), Error> {
+    let wins: Vec<_> = ridings(polls)
+        .iter()
+        .filter(|riding| parties.iter().any(|p| riding.was_winner(p)).not())
+        .filter_map(|riding| {
+            let cs = &riding.candidates;
+            let winner = riding.winner();
+            let combined: Option<usize> = parties.iter().map(|p| cs.get(p).map(|c| c.votes)).sum();
+            cs.get(&winner)
+                .zip(combined)
+                .map(|(win, combined)| (riding, winner, win, combined))
+        })
+        .filter(|(_, _, w, combined)| *combined > w.votes)
+        .map(|(riding, wp, w, combined)| ComboVictory {
+            riding: riding.name.clone(),
+            district: riding.district,
+            winner: wp.clone(),
+            winner_votes: w.votes,
+            combined_votes: combined,
+            difference: combined - w.votes,
+        })
+        .collect();
+
+    out.emit(&wins)
+}
+
+/// Vote and seat totals per party, and the kind of government they produce.
+fn totals(unified: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    out.emit(&totals_of(unified))
+}
+
+fn totals_of(unified: Vec<Poll>) -> Totals {
+    let mut votes: HashMap<Party, usize> = HashMap::new();
+    let mut seats: HashMap<Party, usize> = HashMap::new();
+
+    for riding in ridings(unified) {
+        let party = riding.winner();
+        let entry = seats.entry(party).or_insert(0);
+        *entry += 1;
+
+        for (party, candidate) in riding.candidates {
+            let entry = votes.entry(party).or_insert(0);
+            *entry += candidate.votes;
+        }
+    }
+
+    let total_votes: usize = votes.values().sum();
+    let vote_counts: Vec<VoteCount> = votes
+        .into_iter()
+        .map(|(party, votes)| VoteCount {
+            seats: seats.remove(&party).unwrap_or(0),
+            party,
+            votes,
+            ratio: votes as f64 / total_votes as f64,
+        })
+        .collect();
+
+    let total_seats: usize = vote_counts.iter().map(|v| v.seats).sum();
+    let magic_number = total_seats / 2 + 1;
+    let government = vote_counts
+        .iter()
+        .max_by_key(|v| v.seats)
+        .map(|v| Government {
+            party: v.party.clone(),
+            seats: v.seats,
+            magic_number,
+            majority: v.seats >= magic_number,
+            shortfall: magic_number.saturating_sub(v.seats),
+        });
+
+    Totals {
+        government,
+        parties: vote_counts,
+    }
+}
+
+/// How many candidates ran in each riding, and how those counts are
+/// distributed across the country. "Registered" candidates are those from the
+/// major parties; everyone else (small parties and independents) is "fringe".
+fn candidate_counts(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut ridings: Vec<RidingCandidates> = ridings(polls)
+        .into_iter()
+        .map(|riding| {
+            let fringe = riding.individuals().filter(|(p, _)| p.is_fringe()).count();
+            let candidates = riding.individuals().count();
+
+            RidingCandidates {
+                riding: riding.name,
+                district: riding.district,
+                candidates,
+                registered: candidates - fringe,
+                fringe,
+            }
+        })
+        .collect();
+
+    ridings.sort_by_key(|r| Reverse(r.candidates));
+
+    let distribution = ridings
+        .iter()
+        .map(|r| r.candidates)
+        .sorted()
+        .group_by(|c| *c)
+        .into_iter()
+        .map(|(candidates, group)| CandidateFrequency {
+            candidates,
+            ridings: group.count(),
+        })
+        .collect();
+
+    let max = ridings.iter().map(|r| r.candidates).max().unwrap_or(0);
+    let min = ridings.iter().map(|r| r.candidates).min().unwrap_or(0);
+    let named = |n: usize| {
+        ridings
+            .iter()
+            .filter(|r| r.candidates == n)
+            .map(|r| r.riding.clone())
+            .collect()
+    };
+
+    let counts = CandidateCounts {
+        most: named(max),
+        fewest: named(min),
+        distribution,
+        ridings,
+    };
+
+    out.emit(&counts)
+}
+
+/// How incumbents fared: per-party re-election rates, the incumbents who lost,
+/// and the ridings in which no incumbent ran at all.
+fn incumbency(polls: Vec<Poll>, year: usize, out: &mut Output) -> Result<(), Error> {
+    let mut parties: BTreeMap<Party, PartyIncumbents> = BTreeMap::new();
+    let mut defeated = Vec::new();
+    let mut open_seats = Vec::new();
+
+    for riding in ridings(polls) {
+        let winner = riding.winner();
+        let mut open = true;
+
+        for (party, c) in riding.individuals().filter(|(_, c)| c.incumbent) {
+            open = false;
+            let entry = parties
+                .entry(party.clone())
+                .or_insert_with(|| PartyIncumbents::new(party.clone()));
+            entry.incumbents += 1;
+
+            if riding.is_victor(party, c) {
+                entry.reelected += 1;
+            } else {
+                entry.defeated += 1;
+                defeated.push(DefeatedIncumbent {
+                    riding: riding.name.clone(),
+                    district: riding.district,
+                    party: party.clone(),
+                    name: c.name(),
+                    last_name: c.last_name.clone(),
+                    first_name: c.first_name.clone(),
+                    votes: c.votes,
+                    winner: winner.clone(),
+                });
+            }
+        }
+
+        if open {
+            parties
+                .entry(winner.clone())
+                .or_insert_with(|| PartyIncumbents::new(winner))
+                .open_seats_won += 1;
+            open_seats.push(riding.name);
+        }
+    }
+
+    let parties = parties
+        .into_values()
+        .map(|mut p| {
+            if p.incumbents > 0 {
+                p.rate = p.reelected as f64 / p.incumbents as f64;
+            }
+            p
+        })
+        .collect();
+
+    let result = Incumbency {
+        year,
+        parties,
+        defeated,
+        open_seats,
+    };
+
+    out.emit(&result)
+}
+
+/// Compare each riding's computed winner against the candidate that Elections
+/// Canada officially declared elected. Any mismatch points either to a bug in
+/// our aggregation or to a quirk in the data, like a judicial recount.
+fn verify_winners(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let discrepancies: Vec<_> = ridings(polls)
+        .into_iter()
+        .filter_map(|riding| {
+            let (computed, victor) = riding.victor();
+            let official = riding.official_winner();
+            let agree = official.is_some_and(|(p, c)| riding.is_victor(p, c));
+
+            (!agree).then(|| WinnerDiscrepancy {
+                computed_votes: victor.votes,
+                official_votes: official.map(|(_, c)| c.votes),
+                riding: riding.name.clone(),
+                district: riding.district,
+                computed: computed.clone(),
+                official: official.map(|(p, _)| p.clone()),
+            })
+        })
+        .collect();
+
+    out.emit(&discrepancies)
+}
+
+/// A candidate's name as it would be written, with the middle name if they gave
+/// one.
+fn full_name(first: &str, middle: &str, last: &str) -> String {
+    [first, middle, last]
+        .iter()
+        .filter(|n| n.is_empty().not())
+        .join(" ")
+}
+
+/// Compose any accents that were given as separate marks, collapse runs of
+/// spaces, and capitalize names written in all capitals (or none), as some
+/// years' files do. Names in mixed case, like `MacKay` or `de Jong`, are left
+/// as they are.
+fn proper_name(name: &str) -> String {
+    let name = name.nfc().collect::<String>().split_whitespace().join(" ");
+    let has = |f: fn(char) -> bool| name.chars().any(f);
+
+    if has(char::is_lowercase) && has(char::is_uppercase) {
+        return name;
+    }
+
+    let mut capital = true;
+    name.chars()
+        .flat_map(|c| {
+            let out: Vec<char> = if capital {
+                c.to_uppercase().collect()
+            } else {
+                c.to_lowercase().collect()
+            };
+            capital = matches!(c, ' ' | '-' | '\'' | '’');
+            out
+        })
+        .collect()
+}
+
+/// Lowercase a name and strip its accents, so that searches for "cote" find
+/// "Côté".
+fn fold(name: &str) -> String {
+    name.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Every run of every candidate, across all available years.
+fn candidate_runs(settings: &Settings) -> Result<Vec<CandidateRun>, Error> {
+    let mut runs = Vec::new();
+
+    for year in available_years(settings) {
+        for riding in ridings(load(settings, year)?) {
+            let total = riding.total_votes();
+
+            for (party, c) in riding.individuals() {
+                runs.push(CandidateRun {
+                    id: RidingId {
+                        district: riding.district,
+                        year,
+                    },
+                    riding: riding.name.clone(),
+                    won: riding.is_victor(party, c),
+                    party: party.clone(),
+                    name: c.name(),
+                    last_name: c.last_name.clone(),
+                    first_name: c.first_name.clone(),
+                    votes: c.votes,
+                    ratio: c.votes as f64 / total as f64,
+                });
+            }
+        }
+    }
+
+    Ok(runs)
+}
+
+/// Every candidate in every available year whose name contains the query.
+fn candidate_search(settings: &Settings, query: &str, out: &mut Output) -> Result<(), Error> {
+    let query = fold(query);
+    let mut runs: Vec<_> = candidate_runs(settings)?
+        .into_iter()
+        .filter(|r| fold(&r.name).contains(&query))
+        .collect();
+
+    runs.sort_by(|a, b| {
+        (&a.last_name, &a.first_name, a.id).cmp(&(&b.last_name, &b.first_name, b.id))
+    });
+
+    out.emit(&runs)
+}
+
+/// The trajectories of candidates who ran in more than one election, longest
+/// careers first.
+fn career_tracker(settings: &Settings, aliases: &Aliases, out: &mut Output) -> Result<(), Error> {
+    let mut careers: Vec<_> = careers::link(candidate_runs(settings)?, aliases)
+        .into_iter()
+        .filter(|c| c.runs.len() > 1)
+        .collect();
+
+    careers.sort_by_key(|c| Reverse(c.runs.len()));
+
+    out.emit(&careers)
+}
+
+/// How a [`Party`]'s vote share is distributed across the ridings it ran in,
+/// binned for plotting. A party whose support is broad but shallow will have
+/// its ridings bunched into a few low bins, while a regional party will have a
+/// long tail.
+fn share_histogram(
+    polls: Vec<Poll>,
+    party: &Party,
+    width: f64,
+    out: &mut Output,
+) -> Result<(), Error> {
+    if !(width > 0.0 && width <= 1.0) {
+        let msg = "The bin width must be between 0 and 1";
+        return Err(Error::new(ErrorKind::InvalidInput, msg));
+    }
+
+    let count = (1.0 / width).ceil() as usize;
+    // Rounded, so that bins read as 0.15 and not 0.15000000000000002.
+    let edge = |i: usize| ((i as f64 * width).min(1.0) * 1e9).round() / 1e9;
+    let mut bins: Vec<HistogramBin> = (0..count)
+        .map(|i| HistogramBin {
+            from: edge(i),
+            to: edge(i + 1),
+            ridings: 0,
+        })
+        .collect();
+
+    for share in ridings(polls).iter().filter_map(|r| r.share(party)) {
+        // A 100% share belongs in the last bin, not one past it.
+        let i = ((share / width) as usize).min(count - 1);
+        bins[i].ridings += 1;
+    }
+
+    out.emit(&bins)
+}
+
+/// Candidates whose share of the vote fell below the given threshold. Under
+/// the old deposit rules, these candidates would have forfeited their deposit.
+fn lost_deposits(polls: Vec<Poll>, threshold: f64, out: &mut Output) -> Result<(), Error> {
+    let mut parties: BTreeMap<Party, PartyDeposits> = BTreeMap::new();
+    let mut lost_deposits = Vec::new();
+
+    for riding in ridings(polls) {
+        let total = riding.total_votes();
+
+        for (party, c) in riding.individuals() {
+            let ratio = c.votes as f64 / total as f64;
+            let entry = parties.entry(party.clone()).or_insert(PartyDeposits {
+                party: party.clone(),
+                candidates: 0,
+                lost: 0,
+            });
+            entry.candidates += 1;
+
+            if ratio < threshold {
+                entry.lost += 1;
+                lost_deposits.push(LostDeposit {
+                    riding: riding.name.clone(),
+                    district: riding.district,
+                    party: party.clone(),
+                    name: c.name(),
+                    last_name: c.last_name.clone(),
+                    first_name: c.first_name.clone(),
+                    votes: c.votes,
+                    ratio,
+                });
+            }
+        }
+    }
+
+    lost_deposits.sort_by(|a, b| a.ratio.total_cmp(&b.ratio));
+
+    let parties: Vec<_> = parties.into_values().collect();
+    let deposits = Deposits {
+        threshold,
+        candidates: parties.iter().map(|p| p.candidates).sum(),
+        lost: lost_deposits.len(),
+        parties,
+        lost_deposits,
+    };
+
+    out.emit(&deposits)
+}
+
+/// Candidates who changed their banner between elections, with their results
+/// under each.
+fn party_switchers(settings: &Settings, aliases: &Aliases, out: &mut Output) -> Result<(), Error> {
+    let switchers: Vec<_> = careers::link(candidate_runs(settings)?, aliases)
+        .into_iter()
+        .filter(|c| c.runs.iter().map(|r| &r.party).unique().count() > 1)
+        .collect();
+
+    out.emit(&switchers)
+}
+
+/// Ridings that changed hands between two elections.
+fn seat_flips(
+    settings: &Settings,
+    before: usize,
+    after: usize,
+    aliases: &Aliases,
+    out: &mut Output,
+) -> Result<(), Error> {
+    let flips: Vec<_> = pair_ridings(
+        settings,
+        ridings(load(settings, before)?),
+        before,
+        ridings(load(settings, after)?),
+        after,
+        aliases,
+    )?
+    .into_iter()
+    .filter_map(|(b, a)| {
+        let before_winner = b.winner();
+        let after_winner = a.winner();
+
+        (before_winner != after_winner).then(|| SeatFlip {
+            before: RidingId {
+                district: b.district,
+                year: before,
+            },
+            after: RidingId {
+                district: a.district,
+                year: after,
+            },
+            before_margin: b.victory_margin(),
+            after_margin: a.victory_margin(),
+            riding: a.name,
+            before_winner,
+            after_winner,
+        })
+    })
+    .collect();
+
+    out.emit(&flips)
+}
+
+/// The change in each party's share of the vote in every riding it contested
+/// in both elections. Swings toward and away from each party are listed
+/// separately, largest first.
+fn swings(
+    settings: &Settings,
+    before: usize,
+    after: usize,
+    aliases: &Aliases,
+    out: &mut Output,
+) -> Result<(), Error> {
+    let mut parties: BTreeMap<Party, Vec<Swing>> = BTreeMap::new();
+    let pairs = pair_ridings(
+        settings,
+        ridings(load(settings, before)?),
+        before,
+        ridings(load(settings, after)?),
+        after,
+        aliases,
+    )?;
+
+    for (b, a) in pairs {
+        for party in a.candidates.keys() {
+            if let (Some(before), Some(after)) = (b.share(party), a.share(party)) {
+                parties.entry(party.clone()).or_default().push(Swing {
+                    riding: a.name.clone(),
+                    district: a.district,
+                    swing: after - before,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    let swings: Vec<_> = parties
+        .into_iter()
+        .map(|(party, mut swings)| {
+            swings.sort_by(|a, b| b.swing.total_cmp(&a.swing));
+            let split = swings.iter().take_while(|s| s.swing > 0.0).count();
+            let mut away = swings.split_off(split);
+            away.reverse();
+
+            PartySwings {
+                party,
+                toward: swings,
+                away,
+            }
+        })
+        .collect();
+
+    out.emit(&swings)
+}
+
+/// Career statistics for candidates who ran more than once: their longest run
+/// of consecutive wins, how long it took them to first get elected, and their
+/// biggest change in fortune between attempts.
+fn career_streaks(settings: &Settings, aliases: &Aliases, out: &mut Output) -> Result<(), Error> {
+    let mut stats: Vec<_> = careers::link(candidate_runs(settings)?, aliases)
+        .into_iter()
+        .filter(|c| c.runs.len() > 1)
+        .map(|c| CareerStats {
+            runs: c.runs.len(),
+            wins: c.runs.iter().filter(|r| r.won).count(),
+            longest_streak: c.longest_streak(),
+            losses_before_first_win: c.losses_before_first_win(),
+            largest_swing: c.largest_swing(),
+            name: c.name,
+            first_name: c.first_name,
+            last_name: c.last_name,
+        })
+        .collect();
+
+    stats.sort_by_key(|s| Reverse((s.longest_streak, s.wins)));
+
+    out.emit(&stats)
+}
+
+/// For each riding, how many voters switching from the winner to the runner-up
+/// (or staying home) would have changed the result, closest first. Also finds
+/// the fewest such switches, across all ridings, that would have changed which
+/// party won the most seats, and that would have denied or granted a majority.
+fn decisive_votes(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let ridings = ridings(polls);
+    let mut seats: HashMap<Party, usize> = HashMap::new();
+
+    for riding in ridings.iter() {
+        *seats.entry(riding.winner()).or_insert(0) += 1;
+    }
+
+    let mut decisive: Vec<_> = ridings
+        .iter()
+        .filter_map(|r| {
+            r.runner_up().map(|runner_up| DecisiveVotes {
+                riding: r.name.clone(),
+                district: r.district,
+                winner: r.winner(),
+                votes: r.decisive_votes(),
+                abstentions: r.abstentions_to_flip(),
+                runner_up,
+            })
+        })
+        .collect();
+
+    decisive.sort_by_key(|d| d.votes);
+
+    let leader = seats
+        .iter()
+        .max_by_key(|(_, s)| **s)
+        .map(|(p, _)| p.clone())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No ridings found"))?;
+
+    let to_change_leader = seats
+        .keys()
+        .filter(|p| **p != leader)
+        .filter_map(|challenger| {
+            // The challenger has to end up with strictly more seats.
+            let needed = seats[&leader] - seats[challenger] + 1;
+            overtake_cost(&decisive, &leader, challenger, needed).map(|(votes, flips)| {
+                LeaderChange {
+                    challenger: challenger.clone(),
+                    votes,
+                    ridings: flips.iter().map(|d| d.riding.clone()).collect(),
+                }
+            })
+        })
+        .min_by_key(|c| c.votes);
+
+    let magic_number = ridings.len() / 2 + 1;
+    let leader_seats = seats[&leader];
+    let (to_deny_majority, to_grant_majority) = if leader_seats >= magic_number {
+        let needed = leader_seats - magic_number + 1;
+        let deny = seat_change_cost(&decisive, needed, |d| d.winner == leader);
+        (deny, None)
+    } else {
+        // Parties that won no seats at all might still be runners-up.
+        let grant = decisive
+            .iter()
+            .map(|d| &d.runner_up)
+            .unique()
+            .filter_map(|party| {
+                let needed = magic_number - seats.get(party).copied().unwrap_or(0);
+                seat_change_cost(&decisive, needed, |d| &d.runner_up == party).map(|change| {
+                    MajorityChange {
+                        party: party.clone(),
+                        change,
+                    }
+                })
+            })
+            .min_by_key(|m| m.change.votes);
+        (None, grant)
+    };
+
+    let result = Decisive {
+        leader,
+        magic_number,
+        to_change_leader,
+        to_deny_majority,
+        to_grant_majority,
+        ridings: decisive,
+    };
+
+    out.emit(&result)
+}
+
+/// The fewest switching voters needed to close a seat gap of `needed` between
+/// the leader and a challenger, and the ridings where they'd have to switch.
+/// Flipping one of the leader's seats to the challenger closes the gap by two;
+/// flipping a leader's seat to someone else, or someone else's seat to the
+/// challenger, closes it by one.
+fn overtake_cost<'a>(
+    decisive: &'a [DecisiveVotes],
+    leader: &Party,
+    challenger: &Party,
+    needed: usize,
+) -> Option<(usize, Vec<&'a DecisiveVotes>)> {
+    let (doubles, singles): (Vec<_>, Vec<_>) = decisive
+        .iter()
+        .filter(|d| &d.winner == leader || &d.runner_up == challenger)
+        .partition(|d| &d.winner == leader && &d.runner_up == challenger);
+
+    // Both lists are already sorted, cheapest first.
+    let cost = |ds: &[&DecisiveVotes]| ds.iter().map(|d| d.votes).sum::<usize>();
+
+    (0..=doubles.len().min(needed / 2 + 1))
+        .filter_map(|k| {
+            let rest = needed.saturating_sub(2 * k);
+            (rest <= singles.len()).then(|| (cost(&doubles[..k]) + cost(&singles[..rest]), k, rest))
+        })
+        .min_by_key(|(votes, _, _)| *votes)
+        .map(|(votes, k, rest)| {
+            let flips = doubles[..k]
+                .iter()
+                .chain(&singles[..rest])
+                .copied()
+                .collect();
+            (votes, flips)
+        })
+}
+
+/// The fewest switching voters needed to move `needed` seats to (or from) a
+/// party, and the ridings where they'd have to switch.
+fn seat_change_cost<F>(decisive: &[DecisiveVotes], needed: usize, pred: F) -> Option<SeatChange>
+where
+    F: Fn(&DecisiveVotes) -> bool,
+{
+    // Already sorted, cheapest first.
+    let flips: Vec<_> = decisive.iter().filter(|d| pred(d)).take(needed).collect();
+
+    (flips.len() == needed).then(|| SeatChange {
+        votes: flips.iter().map(|d| d.votes).sum(),
+        ridings: flips.iter().map(|d| d.riding.clone()).collect(),
+    })
+}
+
+/// The position of the riding best matching the given name. An exact
+/// (accent-insensitive) match on any of the riding's known names is preferred,
+/// but failing that the first riding whose name contains the query is used.
+fn find_riding(ridings: &[Riding], query: &str, aliases: &Aliases) -> Option<usize> {
+    let key = aliases.key(query);
+    let query = fold(query);
+
+    ridings
+        .iter()
+        .position(|r| aliases.key(&r.name) == key)
+        .or_else(|| ridings.iter().position(|r| fold(&r.name).contains(&query)))
+}
+
+/// A riding's results in every available year.
+fn riding_history(
+    settings: &Settings,
+    query: &str,
+    aliases: &Aliases,
+    out: &mut Output,
+) -> Result<(), Error> {
+    let mut history = Vec::new();
+
+    for year in available_years(settings) {
+        let mut ridings = ridings(load(settings, year)?);
+
+        if let Some(i) = find_riding(&ridings, query, aliases) {
+            let riding = ridings.swap_remove(i);
+            let shares = riding
+                .candidates
+                .keys()
+                .filter_map(|p| riding.share(p).map(|s| (p.clone(), s)))
+                .collect();
+
+            history.push(HistoryEntry {
+                id: RidingId {
+                    district: riding.district,
+                    year,
+                },
+                winner: riding.winner(),
+                margin: riding.victory_margin(),
+                turnout: riding.turnout(),
+                riding: riding.name,
+                shares,
+            });
+        }
+    }
+
+    out.emit(&history)
+}
+
+/// Everything there is to know about one riding in one election, with its
+/// candidates ranked by votes received.
+fn riding_profile(
+    polls: Vec<Poll>,
+    query: &str,
+    year: usize,
+    aliases: &Aliases,
+    out: &mut Output,
+) -> Result<(), Error> {
+    let ridings = ridings(polls);
+    let riding = find_riding(&ridings, query, aliases)
+        .map(|i| &ridings[i])
+        .ok_or_else(|| {
+            let msg = format!("No riding matching {} in {}", query, year);
+            Error::new(ErrorKind::NotFound, msg)
+        })?;
+
+    out.emit(&profile_of(riding, year))
+}
+
+/// A riding's profile, with its candidates ranked by votes received.
+fn profile_of(riding: &Riding, year: usize) -> RidingProfile {
+    let total = riding.total_votes();
+
+    let candidates = riding
+        .placings()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (party, c))| ProfileCandidate {
+            rank: i + 1,
+            party: party.clone(),
+            name: c.name(),
+            last_name: c.last_name.clone(),
+            first_name: c.first_name.clone(),
+            votes: c.votes,
+            ratio: c.votes as f64 / total as f64,
+            incumbent: c.incumbent,
+            elected: c.elected,
+        })
+        .collect();
+
+    RidingProfile {
+        id: RidingId {
+            district: riding.district,
+            year,
+        },
+        riding: riding.name.clone(),
+        winner: riding.winner(),
+        margin: riding.victory_margin(),
+        total_votes: total,
+        rejected: riding.rejected,
+        electors: riding.electors,
+        turnout: riding.turnout(),
+        candidates,
+    }
+}
+
+/// The Bloc against the federalist parties, riding by riding across Quebec.
+/// Also lists the seats the Bloc won only because the non-Bloc vote was split.
+fn quebec(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let ridings: Vec<_> = ridings(polls)
+        .into_iter()
+        .filter(|r| r.province() == Some(Province::QC))
+        .map(|r| {
+            let total = r.total_votes() as f64;
+            let bloc_votes = r.combined_votes(|p| p == &Party::BLQ);
+            let federalist_votes = r.combined_votes(Party::is_federalist);
+
+            QuebecRiding {
+                winner: r.winner(),
+                bloc_ratio: bloc_votes as f64 / total,
+                federalist_ratio: federalist_votes as f64 / total,
+                non_bloc_votes: r.total_votes() - bloc_votes,
+                riding: r.name,
+                district: r.district,
+                bloc_votes,
+                federalist_votes,
+            }
+        })
+        .collect();
+
+    let total: usize = ridings
+        .iter()
+        .map(|r| r.bloc_votes + r.non_bloc_votes)
+        .sum();
+    let bloc: usize = ridings.iter().map(|r| r.bloc_votes).sum();
+    let federalist: usize = ridings.iter().map(|r| r.federalist_votes).sum();
+
+    let (split_opposition, ridings): (Vec<_>, Vec<_>) = ridings
+        .into_iter()
+        .partition(|r| r.winner == Party::BLQ && r.non_bloc_votes > r.bloc_votes);
+
+    let all = || split_opposition.iter().chain(ridings.iter());
+    let result = Quebec {
+        bloc_seats: all().filter(|r| r.winner == Party::BLQ).count(),
+        federalist_seats: all().filter(|r| r.winner.is_federalist()).count(),
+        bloc_ratio: bloc as f64 / total as f64,
+        federalist_ratio: federalist as f64 / total as f64,
+        split_opposition,
+        ridings,
+    };
+
+    out.emit(&result)
+}
+
+/// How each party fared among special ballots (those cast by mail, abroad, by
+/// the military, and so on) compared to ballots cast in person, per riding.
+/// Also notes where the special ballots changed who won.
+fn special_ballots(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let ridings: Vec<_> = ridings(polls)
+        .into_iter()
+        .map(|r| {
+            let special_votes: usize = r.candidates.values().map(|c| c.ballots.special).sum();
+            let in_person_votes: usize = r.candidates.values().map(|c| c.ballots.in_person()).sum();
+            let ratio = |votes: usize, total: usize| match total {
+                0 => 0.0,
+                _ => votes as f64 / total as f64,
+            };
+            let shares = r
+                .candidates
+                .iter()
+                .map(|(p, c)| {
+                    let shares = SpecialShares {
+                        special: ratio(c.ballots.special, special_votes),
+                        in_person: ratio(c.ballots.in_person(), in_person_votes),
+                    };
+                    (p.clone(), shares)
+                })
+                .collect();
+            let leader = r.individuals().max_by_key(|(_, c)| c.ballots.in_person());
+            let flipped = leader.is_some_and(|(p, c)| !r.is_victor(p, c));
+            let in_person_leader = leader.map(|(p, _)| p.clone()).unwrap_or_else(|| r.winner());
+
+            SpecialRiding {
+                flipped,
+                winner: r.winner(),
+                riding: r.name,
+                district: r.district,
+                special_votes,
+                in_person_votes,
+                in_person_leader,
+                shares,
+            }
+        })
+        .collect();
+
+    let flipped = ridings
+        .iter()
+        .filter(|r| r.flipped)
+        .map(|r| r.riding.clone())
+        .collect();
+
+    out.emit(&SpecialBallots { flipped, ridings })
+}
+
+/// How each party fared in advance polls compared to election day, per riding,
+/// with the ridings where the two diverged the most first.
+fn advance_voting(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut ridings: Vec<_> = ridings(polls)
+        .into_iter()
+        .map(|r| {
+            let advance_votes: usize = r.candidates.values().map(|c| c.ballots.advance).sum();
+            let election_day_votes: usize = r
+                .candidates
+                .values()
+                .map(|c| c.ballots.election_day())
+                .sum();
+            let ratio = |votes: usize, total: usize| match total {
+                0 => 0.0,
+                _ => votes as f64 / total as f64,
+            };
+            let shares: BTreeMap<_, _> = r
+                .candidates
+                .iter()
+                .map(|(p, c)| {
+                    let advance = ratio(c.ballots.advance, advance_votes);
+                    let election_day = ratio(c.ballots.election_day(), election_day_votes);
+                    let shares = AdvanceShares {
+                        advance,
+                        election_day,
+                        difference: advance - election_day,
+                    };
+                    (p.clone(), shares)
+                })
+                .collect();
+            let divergence = shares.values().map(|s| s.difference.abs()).sum::<f64>() / 2.0;
+
+            AdvanceRiding {
+                riding: r.name,
+                district: r.district,
+                advance_votes,
+                election_day_votes,
+                divergence,
+                shares,
+            }
+        })
+        .collect();
+
+    ridings.sort_by(|a, b| b.divergence.total_cmp(&a.divergence));
+
+    out.emit(&ridings)
+}
+
+/// Seat and vote totals per party within each class of riding. Ridings that
+/// the classification doesn't mention are grouped as "unclassified".
+fn class_breakdown(polls: Vec<Poll>, classes: &Classes, out: &mut Output) -> Result<(), Error> {
+    let mut groups: BTreeMap<String, Vec<Riding>> = BTreeMap::new();
+
+    for riding in ridings(polls) {
+        let class = classes
+            .of(riding.district, &riding.name)
+            .unwrap_or("unclassified")
+            .to_string();
+        groups.entry(class).or_default().push(riding);
+    }
+
+    let summaries: Vec<_> = groups
+        .into_iter()
+        .map(|(class, ridings)| {
+            let mut votes: BTreeMap<Party, usize> = BTreeMap::new();
+            let mut seats: HashMap<Party, usize> = HashMap::new();
+
+            for riding in ridings.iter() {
+                *seats.entry(riding.winner()).or_insert(0) += 1;
+
+                for (party, c) in riding.candidates.iter() {
+                    *votes.entry(party.clone()).or_insert(0) += c.votes;
+                }
+            }
+
+            let total: usize = votes.values().sum();
+            let parties = votes
+                .into_iter()
+                .map(|(party, votes)| VoteCount {
+                    seats: seats.remove(&party).unwrap_or(0),
+                    party,
+                    votes,
+                    ratio: votes as f64 / total as f64,
+                })
+                .collect();
+
+            ClassSummary {
+                class,
+                ridings: ridings.len(),
+                parties,
+            }
+        })
+        .collect();
+
+    out.emit(&summaries)
+}
+
+/// Ridings where the candidate leading among election-day ballots was overtaken
+/// once advance-poll and special ballots were added in.
+fn late_flips(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let flips: Vec<_> = ridings(polls)
+        .into_iter()
+        .filter_map(|r| {
+            let mut day: Vec<_> = r.individuals().collect();
+            day.sort_by_key(|(_, c)| Reverse(c.ballots.election_day()));
+
+            let (leader, lead) = match day.as_slice() {
+                [(p, a), (_, b), ..] if !r.is_victor(p, a) => (
+                    (*p).clone(),
+                    a.ballots.election_day() - b.ballots.election_day(),
+                ),
+                _ => return None,
+            };
+            let winner = r.winner();
+
+            Some(LateFlip {
+                advance_votes: r.candidates.values().map(|c| c.ballots.advance).sum(),
+                special_votes: r.candidates.values().map(|c| c.ballots.special).sum(),
+                riding: r.name,
+                district: r.district,
+                election_day_leader: leader,
+                election_day_lead: lead,
+                winner,
+            })
+        })
+        .collect();
+
+    out.emit(&flips)
+}
+
+/// How many seats each party won with over half of the vote, and how many with
+/// only a plurality.
+fn majorities(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut parties: HashMap<Party, PartyMajorities> = HashMap::new();
+
+    for riding in ridings(polls) {
+        let winner = riding.winner();
+        let majority = riding.share(&winner).unwrap_or(0.0) > 0.5;
+        let entry = parties.entry(winner.clone()).or_insert(PartyMajorities {
+            party: winner,
+            seats: 0,
+            majority: 0,
+            plurality: 0,
+        });
+        entry.seats += 1;
+
+        if majority {
+            entry.majority += 1;
+        } else {
+            entry.plurality += 1;
+        }
+    }
+
+    let mut parties: Vec<_> = parties.into_values().collect();
+    parties.sort_by_key(|p| Reverse(p.seats));
+
+    let seats = parties.iter().map(|p| p.seats).sum();
+    let plurality = parties.iter().map(|p| p.plurality).sum();
+    let majorities = Majorities {
+        seats,
+        plurality,
+        plurality_ratio: plurality as f64 / seats as f64,
+        parties,
+    };
+
+    out.emit(&majorities)
 }
 
-#[derive(Serialize)]
-struct PartyResults {
-    riding: String,
-    party: Party,
-    last_name: String,
-    first_name: String,
-    votes: usize,
-    ratio: f32,
-    won: bool,
+/// A [`Party`]'s best and worst ridings by share of the vote, strongest and
+/// weakest first respectively.
+fn heartlands(
+    polls: Vec<Poll>,
+    party: &Party,
+    count: usize,
+    out: &mut Output,
+) -> Result<(), Error> {
+    let results = results_of(polls, party);
+    let contested = results.len();
+    let weakest = results[..count.min(contested)].to_vec();
+    let strongest = results.into_iter().rev().take(count).collect();
+
+    out.emit(&Heartlands {
+        party: party.clone(),
+        contested,
+        strongest,
+        weakest,
+    })
 }
 
-fn main() -> Result<(), std::io::Error> {
-    let args = Args::parse();
-    let data = format!("data/{}", args.year);
+/// Each party's seats as one block of a half-donut parliament chart, ordered
+/// from the left of the chamber to the right.
+fn hemicycle(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    out.emit(&blocks_of(polls))
+}
 
-    let mut polls: Vec<Poll> = std::fs::read_dir(data)?
-        .filter_map(|de| de.ok())
-        .filter_map(|de| csv::Reader::from_path(de.path()).ok())
-        // Unfortunate `collect` due to the `reader` being owned.
-        .flat_map(|mut reader| reader.deserialize::<Poll>().collect::<Vec<_>>().into_iter())
-        .collect::<Result<Vec<Poll>, _>>()?;
+fn blocks_of(polls: Vec<Poll>) -> Vec<SeatBlock> {
+    let mut seats: HashMap<Party, usize> = HashMap::new();
 
-    // Sort by riding, then party.
-    polls.sort();
+    for riding in ridings(polls) {
+        *seats.entry(riding.winner()).or_insert(0) += 1;
+    }
 
-    let unified: Vec<Poll> = polls
-        .into_iter()
-        // `clone` of enums is cheap.
-        .group_by(|poll| poll.party.clone())
+    seats
         .into_iter()
-        .filter_map(|(_, group)| group.reduce(|a, b| a.fuse(b)))
-        .collect();
+        .sorted_by_key(|(p, n)| (p.seating(), Reverse(*n), p.clone()))
+        .enumerate()
+        .map(|(order, (party, seats))| SeatBlock {
+            order,
+            colour: party.colour(),
+            party,
+            seats,
+        })
+        .collect()
+}
+
+/// For each election, whether the popular vote and the seat count were won by
+/// the same party.
+fn inversions(settings: &Settings, out: &mut Output) -> Result<(), Error> {
+    let mut inversions = Vec::new();
+
+    for year in available_years(settings) {
+        let mut votes: HashMap<Party, usize> = HashMap::new();
+        let mut seats: HashMap<Party, usize> = HashMap::new();
+
+        for riding in ridings(load(settings, year)?) {
+            *seats.entry(riding.winner()).or_insert(0) += 1;
+
+            for (party, c) in riding.candidates {
+                *votes.entry(party).or_insert(0) += c.votes;
+            }
+        }
+
+        let total: usize = votes.values().sum();
+        let vote_winner = votes.iter().max_by_key(|(_, v)| **v).map(|(p, _)| p);
+        let seat_winner = seats.iter().max_by_key(|(_, s)| **s).map(|(p, _)| p);
+
+        if let (Some(vw), Some(sw)) = (vote_winner, seat_winner) {
+            let ratio = |p: &Party| votes.get(p).copied().unwrap_or(0) as f64 / total as f64;
 
-    if args.total {
-        totals(unified);
-    } else if args.conppc {
-        ppc_con(unified);
-    } else if args.margins {
-        victory_margins(unified);
-    } else if let Some(party) = args.party {
-        party_results(unified, party);
+            inversions.push(Inversion {
+                year,
+                vote_winner: vw.clone(),
+                vote_winner_ratio: ratio(vw),
+                vote_winner_seats: seats.get(vw).copied().unwrap_or(0),
+                seat_winner: sw.clone(),
+                seat_winner_ratio: ratio(sw),
+                seat_winner_seats: seats[sw],
+                inverted: vw != sw,
+            });
+        }
     }
 
-    Ok(())
+    out.emit(&inversions)
 }
 
-fn ridings(polls: Vec<Poll>) -> Vec<Riding> {
-    polls
-        .into_iter()
-        .group_by(|poll| poll.riding.clone())
+/// The average and median victory margin of each party in the seats it won,
+/// shakiest caucus first.
+fn caucus_margins(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut margins: HashMap<Party, Vec<f64>> = HashMap::new();
+
+    for riding in ridings(polls) {
+        margins
+            .entry(riding.winner())
+            .or_default()
+            .push(riding.victory_margin());
+    }
+
+    let mut caucuses: Vec<_> = margins
         .into_iter()
-        .map(|(name, group)| {
-            let candidates = group
-                .map(|poll| {
-                    let p = poll.party;
-                    let c = Candidate {
-                        last_name: poll.last_name,
-                        first_name: poll.first_name,
-                        votes: poll.votes,
-                    };
+        .map(|(party, mut ms)| {
+            ms.sort_by(|a, b| a.total_cmp(b));
+            let n = ms.len();
+            let median = if n % 2 == 0 {
+                (ms[n / 2 - 1] + ms[n / 2]) / 2.0
+            } else {
+                ms[n / 2]
+            };
+
+            CaucusMargins {
+                party,
+                seats: n,
+                average: ms.iter().sum::<f64>() / n as f64,
+                median,
+                closest: ms[0],
+            }
+        })
+        .collect();
 
-                    (p, c)
+    caucuses.sort_by(|a, b| a.average.total_cmp(&b.average));
+
+    out.emit(&caucuses)
+}
+
+/// Where each party over- and underperforms: its share of the vote in each
+/// province, divided by its share nationally.
+fn fingerprint(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut votes: BTreeMap<Province, HashMap<Party, usize>> = BTreeMap::new();
+
+    for riding in ridings(polls) {
+        if let Some(province) = riding.province() {
+            let entry = votes.entry(province).or_default();
+
+            for (party, c) in riding.candidates {
+                *entry.entry(party).or_insert(0) += c.votes;
+            }
+        }
+    }
+
+    let mut national: HashMap<&Party, usize> = HashMap::new();
+
+    for (party, v) in votes.values().flatten() {
+        *national.entry(party).or_insert(0) += v;
+    }
+
+    let total: usize = national.values().sum();
+    let provinces: Vec<_> = votes.keys().copied().collect();
+    let mut parties: Vec<_> = national
+        .iter()
+        .map(|(party, v)| {
+            let share = *v as f64 / total as f64;
+            let relative = votes
+                .values()
+                .map(|pv| {
+                    let here = pv.get(party).copied().unwrap_or(0) as f64;
+                    here / pv.values().sum::<usize>() as f64 / share
                 })
                 .collect();
 
-            Riding { name, candidates }
+            PartyFingerprint {
+                party: (*party).clone(),
+                national: share,
+                relative,
+            }
         })
-        .collect()
+        .collect();
+
+    parties.sort_by(|a, b| b.national.total_cmp(&a.national));
+
+    out.emit(&Fingerprint { provinces, parties })
 }
 
-/// How a given [`Party`] did in every riding.
-fn party_results(polls: Vec<Poll>, party: Party) {
-    let mut results: Vec<_> = ridings(polls)
+/// Where each party did and didn't run a candidate, widest coverage first.
+fn coverage(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let ridings = ridings(polls);
+    let mut shares: HashMap<&Party, Vec<f64>> = HashMap::new();
+
+    for riding in ridings.iter() {
+        for party in riding.candidates.keys() {
+            if let Some(share) = riding.share(party) {
+                shares.entry(party).or_default().push(share);
+            }
+        }
+    }
+
+    let mut coverage: Vec<_> = shares
         .into_iter()
-        .filter_map(|mut riding| {
-            let won = riding.was_winner(&party);
-            riding.candidates.remove(&party).map(|c| (riding, won, c))
+        .map(|(party, ss)| Coverage {
+            party: party.clone(),
+            contested: ss.len(),
+            coverage: ss.len() as f64 / ridings.len() as f64,
+            average_ratio: ss.iter().sum::<f64>() / ss.len() as f64,
+            skipped: ridings
+                .iter()
+                .filter(|r| !r.candidates.contains_key(party))
+                .map(|r| r.name.clone())
+                .collect(),
         })
-        .map(|(riding, won, c)| {
-            //
-            let total = riding.total_votes() + c.votes;
-            let ratio = c.votes as f32 / total as f32;
+        .collect();
 
-            PartyResults {
-                riding: riding.name,
-                party: party.clone(),
+    coverage.sort_by(|a, b| {
+        b.contested
+            .cmp(&a.contested)
+            .then(b.average_ratio.total_cmp(&a.average_ratio))
+    });
+
+    out.emit(&coverage)
+}
+
+/// Ridings where the given party finished out of the top two with more votes
+/// than separated them, and so plausibly decided the result. Those where it
+/// mattered most come first.
+fn spoilers(polls: Vec<Poll>, party: &Party, out: &mut Output) -> Result<(), Error> {
+    let mut spoilers: Vec<_> = ridings(polls)
+        .into_iter()
+        .filter_map(|r| {
+            let votes = r.candidates.get(party)?.votes;
+
+            match r.placings().as_slice() {
+                [(wp, w), (rp, ru), ..] if *wp != party && *rp != party => {
+                    let margin = w.votes - ru.votes;
+
+                    (votes > margin).then(|| Spoiler {
+                        riding: r.name.clone(),
+                        district: r.district,
+                        party: party.clone(),
+                        votes,
+                        winner: (*wp).clone(),
+                        winner_votes: w.votes,
+                        runner_up: (*rp).clone(),
+                        runner_up_votes: ru.votes,
+                        margin,
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect();
+
+    spoilers.sort_by_key(|s| Reverse(s.votes - s.margin));
+
+    out.emit(&spoilers)
+}
+
+/// Every independent and unaffiliated candidate, best result first. Unlike
+/// other analyses, several such candidates in the same riding are kept apart.
+fn independents(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut results = Vec::new();
+    let mut ridings_run = 0;
+
+    for riding in ridings(polls) {
+        if riding.unaffiliated.is_empty() {
+            continue;
+        }
+
+        ridings_run += 1;
+
+        let total = riding.total_votes();
+        let others: Vec<usize> = riding.individuals().map(|(_, c)| c.votes).collect();
+
+        for (party, c) in riding.unaffiliated {
+            let placing = 1 + others.iter().filter(|v| **v > c.votes).count();
+
+            results.push(IndependentResult {
+                riding: riding.name.clone(),
+                district: riding.district,
+                party,
+                name: c.name(),
                 last_name: c.last_name,
                 first_name: c.first_name,
                 votes: c.votes,
-                ratio,
-                won,
+                ratio: c.votes as f64 / total as f64,
+                placing,
+                won: placing == 1,
+            });
+        }
+    }
+
+    results.sort_by(|a, b| b.ratio.total_cmp(&a.ratio));
+
+    out.emit(&Independents {
+        candidates: results.len(),
+        ridings: ridings_run,
+        won: results.iter().filter(|r| r.won).count(),
+        results,
+    })
+}
+
+/// Each riding's vote concentration, from the most fragmented contest to the
+/// least.
+fn fragmentation(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut ridings: Vec<_> = ridings(polls)
+        .into_iter()
+        .map(|r| {
+            let hhi = r.concentration();
+
+            Fragmentation {
+                winner: r.winner(),
+                margin: r.victory_margin(),
+                riding: r.name,
+                district: r.district,
+                hhi,
+                effective_candidates: 1.0 / hhi,
             }
         })
         .collect();
 
-    results.sort_by(|a, b| a.ratio.partial_cmp(&b.ratio).unwrap_or(Ordering::Less));
+    ridings.sort_by(|a, b| a.hhi.total_cmp(&b.hhi));
 
-    println!("{}", serde_json::to_string(&results).unwrap());
+    out.emit(&ridings)
 }
 
-/// Ordered list of ridings by the victory margin.
-fn victory_margins(polls: Vec<Poll>) {
-    let mut margins: Vec<_> = ridings(polls)
+/// How contested each province was, most competitive first.
+fn competitiveness(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut margins: BTreeMap<Province, Vec<f64>> = BTreeMap::new();
+
+    for riding in ridings(polls) {
+        if let Some(province) = riding.province() {
+            margins
+                .entry(province)
+                .or_default()
+                .push(riding.victory_margin());
+        }
+    }
+
+    let mut provinces: Vec<_> = margins
         .into_iter()
-        .map(|riding| {
-            let margin = riding.victory_margin();
-            let winner = riding.winner();
+        .map(|(province, ms)| {
+            let seats = ms.len();
+            let average_margin = ms.iter().sum::<f64>() / seats as f64;
+            let close_seats = ms.iter().filter(|m| **m < CLOSE_MARGIN).count();
+            let close_ratio = close_seats as f64 / seats as f64;
 
-            VictoryMargin {
-                winner: winner.clone(),
-                riding: riding.name,
-                margin,
+            Competitiveness {
+                province,
+                seats,
+                average_margin,
+                close_seats,
+                close_ratio,
+                index: (close_ratio + 1.0 - average_margin) / 2.0,
             }
         })
         .collect();
 
-    margins.sort_by(|a, b| a.margin.partial_cmp(&b.margin).unwrap_or(Ordering::Less));
+    provinces.sort_by(|a, b| b.index.total_cmp(&a.index));
 
-    println!("{}", serde_json::to_string(&margins).unwrap());
+    out.emit(&provinces)
 }
 
-/// For ridings in which the Conservatives lost, would the combined CON + PPC
-/// have swung the result?
-///
-/// False Assumption #1: All PPC voters are naturally right-wing and would have
-/// otherwise voted CON. Similar to Trump voters in the USA, a section of the
-/// voter base are those disenfranchised with the existing parties and who just
-/// want a new alternative. While right-wing in nature, surely the PPC are
-/// drawing voters from all parts of Canada.
-///
-/// False Assumption #2: Everyone has a fixed party loyalty, and nobody ever
-/// votes for other reasons. In reality there are a myriad of reasons why people
-/// choose a particular party to vote for in a particular riding in a particular
-/// year.
-fn ppc_con(polls: Vec<Poll>) {
-    let wins: Vec<_> = ridings(polls)
-        .iter()
-        .filter(|riding| riding.was_winner(&Party::CON).not())
-        .filter_map(|riding| {
-            let cs = &riding.candidates;
-            let winner = riding.winner();
-            cs.get(&winner).and_then(|win| {
-                cs.get(&Party::CON).and_then(|con| {
-                    cs.get(&Party::PPC)
-                        .map(|ppc| (riding, winner, win, con, ppc))
+/// Each candidate's result in each poll, without fusing anything. Polls that
+/// were merged into another report no votes of their own, and are skipped.
+fn poll_results(mut polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    polls.sort_by(|a, b| (a.district, &a.number).cmp(&(b.district, &b.number)));
+
+    let results: Vec<_> = polls
+        .into_iter()
+        .group_by(|p| (p.district, p.number.clone()))
+        .into_iter()
+        .flat_map(|(_, group)| {
+            let group: Vec<Poll> = group.collect();
+            let total: usize = group.iter().map(|p| p.votes).sum();
+            let best = group.iter().map(|p| p.votes).max().unwrap_or(0);
+
+            group
+                .into_iter()
+                .filter(move |_| total > 0)
+                .map(move |p| PollResult {
+                    ratio: p.votes as f64 / total as f64,
+                    won: p.votes == best,
+                    name: p.name(),
+                    riding: p.riding,
+                    district: p.district,
+                    number: p.number,
+                    station: p.station,
+                    electors: p.electors,
+                    rejected: p.rejected,
+                    party: p.party,
+                    last_name: p.last_name,
+                    first_name: p.first_name,
+                    votes: p.votes,
                 })
+        })
+        .collect();
+
+    out.emit(&results)
+}
+
+/// How much turnout varied between the polls of each riding, most varied
+/// first. Only polls with their own electors (so not advance or special
+/// ballots) have a turnout.
+fn poll_turnout(mut polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    polls.sort_by(|a, b| (a.district, &a.number).cmp(&(b.district, &b.number)));
+
+    let mut ridings: Vec<_> = polls
+        .into_iter()
+        .group_by(|p| (p.district, p.number.clone()))
+        .into_iter()
+        .filter_map(|(_, group)| {
+            let group: Vec<Poll> = group.collect();
+            let first = group.first()?;
+            let votes: usize = group.iter().map(|p| p.votes).sum();
+
+            (first.electors > 0).then(|| {
+                let turnout = PollTurnout {
+                    number: first.number.clone(),
+                    station: first.station.clone(),
+                    electors: first.electors,
+                    turnout: (votes + first.rejected) as f64 / first.electors as f64,
+                };
+                ((first.district, first.riding.clone()), turnout)
             })
         })
-        .filter(|(_, _, w, c, p)| c.votes + p.votes > w.votes)
-        .map(|(riding, wp, w, c, p)| ComboVictory {
-            riding: riding.name.clone(),
-            winner: wp.clone(),
-            winner_votes: w.votes,
-            con_ppc_votes: c.votes + p.votes,
-            difference: (c.votes + p.votes) - w.votes,
+        .into_group_map()
+        .into_iter()
+        .filter_map(|((district, riding), ts)| {
+            let n = ts.len() as f64;
+            let mean = ts.iter().map(|t| t.turnout).sum::<f64>() / n;
+            let variance = ts.iter().map(|t| (t.turnout - mean).powi(2)).sum::<f64>() / n;
+            let highest = ts.iter().max_by(|a, b| a.turnout.total_cmp(&b.turnout))?;
+            let lowest = ts.iter().min_by(|a, b| a.turnout.total_cmp(&b.turnout))?;
+
+            Some(RidingTurnout {
+                riding,
+                district,
+                polls: ts.len(),
+                mean,
+                variance,
+                highest: highest.clone(),
+                lowest: lowest.clone(),
+            })
         })
         .collect();
 
-    println!("{}", serde_json::to_string(&wins).unwrap());
+    ridings.sort_by(|a, b| b.variance.total_cmp(&a.variance));
+
+    out.emit(&ridings)
 }
 
-/// Vote and seat totals per party.
-fn totals(unified: Vec<Poll>) {
-    let mut votes: HashMap<Party, usize> = HashMap::new();
-    let mut seats: HashMap<Party, usize> = HashMap::new();
+/// Each riding's rate of rejected ballots, highest first, noting where there
+/// were enough of them to have decided the result.
+fn rejected_ballots(polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut ridings: Vec<_> = ridings(polls)
+        .into_iter()
+        .map(|r| {
+            let margin = r.vote_margin();
+            let cast = r.total_votes() + r.rejected;
 
-    for riding in ridings(unified) {
-        let party = riding.winner();
-        let entry = seats.entry(party).or_insert(0);
-        *entry += 1;
+            RejectedBallots {
+                // Nothing cast at all, as when no poll was held.
+                rate: match cast {
+                    0 => 0.0,
+                    _ => r.rejected as f64 / cast as f64,
+                },
+                exceeds_margin: r.rejected > margin,
+                winner: r.winner(),
+                riding: r.name,
+                district: r.district,
+                rejected: r.rejected,
+                margin,
+            }
+        })
+        .collect();
 
-        for (party, candidate) in riding.candidates {
-            let entry = votes.entry(party).or_insert(0);
-            *entry += candidate.votes;
+    ridings.sort_by(|a, b| b.rate.total_cmp(&a.rate));
+
+    out.emit(&ridings)
+}
+
+/// Every by-election for which results are present, compared with the general
+/// election before it. Each by-election's results should sit in their own
+/// `data/byelections/YYYY-MM-DD` directory, in the same format as the general
+/// elections.
+fn byelections(settings: &Settings, out: &mut Output) -> Result<(), Error> {
+    let mut dates: Vec<String> = std::fs::read_dir(byelection_dir(settings))?
+        .filter_map(|de| de.ok())
+        .filter(|de| de.path().is_dir())
+        .map(|de| de.file_name().to_string_lossy().into_owned())
+        .collect();
+    dates.sort();
+
+    // By-elections are fought on the boundaries of the general election before
+    // them, so district numbers can be compared directly.
+    let dated: Vec<(String, Option<usize>)> = dates
+        .into_iter()
+        .map(|date| {
+            let general = available_years(settings)
+                .filter(|y| election_day(*y).is_some_and(|d| d < date.as_str()))
+                .last();
+            (date, general)
+        })
+        .collect();
+    let generals: HashMap<usize, Vec<Riding>> = dated
+        .iter()
+        .filter_map(|(_, g)| *g)
+        .unique()
+        .map(|y| load(settings, y).map(|polls| (y, ridings(polls))))
+        .collect::<Result<_, _>>()?;
+    let mut byelections = Vec::new();
+
+    for (date, general) in dated {
+        let polls = fuse(read_polls(&byelection_dir(settings).join(&date))?);
+
+        for riding in ridings(polls) {
+            let previous_winner = general
+                .and_then(|y| generals[&y].iter().find(|r| r.district == riding.district))
+                .map(|r| r.winner());
+            let winner = riding.winner();
+            let total = riding.total_votes();
+            let candidates = riding
+                .placings()
+                .into_iter()
+                .map(|(p, c)| PartyResults {
+                    riding: riding.name.clone(),
+                    district: riding.district,
+                    party: p.clone(),
+                    name: c.name(),
+                    last_name: c.last_name.clone(),
+                    first_name: c.first_name.clone(),
+                    votes: c.votes,
+                    ratio: c.votes as f64 / total as f64,
+                    won: p == &winner,
+                })
+                .collect();
+
+            byelections.push(ByElection {
+                date: date.clone(),
+                general,
+                margin: riding.victory_margin(),
+                flipped: previous_winner.as_ref().is_some_and(|p| p != &winner),
+                riding: riding.name,
+                district: riding.district,
+                winner,
+                previous_winner,
+                candidates,
+            });
         }
     }
 
-    let total_votes: usize = votes.values().sum();
-    let vote_counts: Vec<VoteCount> = votes
+    let timeline = byelections
+        .iter()
+        .filter_map(|b| b.general.map(|g| (g, b)))
+        .into_group_map()
         .into_iter()
-        .map(|(party, votes)| VoteCount {
-            seats: seats.remove(&party).unwrap_or(0),
-            party,
-            votes,
-            ratio: votes as f32 / total_votes as f32,
+        .sorted_by_key(|(g, _)| *g)
+        .map(|(general, bs)| {
+            let mut changes: BTreeMap<Party, isize> = BTreeMap::new();
+
+            for b in bs.iter().filter(|b| b.flipped) {
+                *changes.entry(b.winner.clone()).or_insert(0) += 1;
+
+                if let Some(p) = &b.previous_winner {
+                    *changes.entry(p.clone()).or_insert(0) -= 1;
+                }
+            }
+
+            SeatTimeline {
+                general,
+                byelections: bs.len(),
+                changes,
+            }
         })
         .collect();
 
-    println!("{}", serde_json::to_string(&vote_counts).unwrap());
+    out.emit(&ByElections {
+        byelections,
+        timeline,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(last_name: &str, votes: usize) -> Candidate {
+        Candidate {
+            last_name: last_name.to_string(),
+            first_name: String::new(),
+            middle_name: String::new(),
+            votes,
+            ballots: Ballots::default(),
+            incumbent: false,
+            elected: false,
+        }
+    }
+
+    /// A riding as `ridings` would build it, from each candidate's party,
+    /// name and votes.
+    fn riding(candidates: &[(Party, &str, usize)]) -> Riding {
+        let mut fused: HashMap<Party, Candidate> = HashMap::new();
+        let mut unaffiliated = Vec::new();
+
+        for (party, name, votes) in candidates {
+            let c = candidate(name, *votes);
+
+            if party.is_unaffiliated() {
+                unaffiliated.push((party.clone(), c.clone()));
+            }
+
+            let c = match fused.remove(party) {
+                Some(other) => other.fuse(c),
+                None => c,
+            };
+            fused.insert(party.clone(), c);
+        }
+
+        Riding {
+            name: "Test".to_string(),
+            district: 35001,
+            electors: 0,
+            rejected: 0,
+            candidates: fused,
+            unaffiliated,
+        }
+    }
+
+    fn poll(riding: &str, party: Party, last_name: &str, votes: usize) -> Poll {
+        Poll {
+            district: 35001,
+            riding: riding.to_string(),
+            riding_fr: String::new(),
+            number: "1".to_string(),
+            station: String::new(),
+            void: false,
+            not_held: false,
+            party,
+            last_name: last_name.to_string(),
+            first_name: String::new(),
+            middle_name: String::new(),
+            votes,
+            incumbent: false,
+            elected: false,
+            electors: 0,
+            rejected: 0,
+            ballots: Ballots::default(),
+        }
+    }
+
+    #[test]
+    fn global_options_reach_every_command() {
+        let commands = [
+            "fetch 2019",
+            "validate 2019",
+            "chart seats",
+            "export sqlite results.db",
+            "report",
+            "schema totals",
+            "totals",
+        ];
+
+        for command in commands {
+            let argv = std::iter::once("election")
+                .chain(command.split(' '))
+                .chain(["--data-dir", "/tmp/data", "--corrections", "recounts.csv"])
+                .chain(["--exclude-party", "ppc", "--year", "2015"]);
+
+            assert!(Args::try_parse_from(argv).is_ok(), "{}", command);
+        }
+    }
+
+    #[test]
+    fn global_options_reach_plugins() {
+        let argv = [
+            "election",
+            "--data-dir",
+            "/tmp/data",
+            "foo",
+            "--year",
+            "2015",
+        ];
+        let args = Args::try_parse_from(argv).unwrap();
+
+        assert_eq!(Some(PathBuf::from("/tmp/data")), args.data_dir);
+        assert!(matches!(args.command, Command::Plugin(argv) if argv == ["foo", "--year", "2015"]));
+    }
+
+    #[test]
+    fn parties_read_back_as_written() {
+        for party in Party::all() {
+            let written = serde_json::to_string(&party).unwrap();
+            let read: Party = serde_json::from_str(&written).unwrap();
+
+            assert_eq!(party, read, "{}", written);
+        }
+    }
+
+    #[test]
+    fn provincial_results() {
+        let csv = "Numéro de circonscription,Circonscription,Section de vote,Candidat,Parti politique,Votes valides
+101,Gaspé,1,\"Tremblay, Marie\",Coalition avenir Québec,300
+101,Gaspé,1,Luc Roy,Parti québécois,250
+101,Gaspé,1,Jo Doe,Parti nul,10";
+        let polls = read_csv(csv::Reader::from_reader(csv.as_bytes())).unwrap();
+        let parties: Vec<_> = polls.iter().map(|p| p.party.clone()).collect();
+
+        assert_eq!(vec![Party::CAQ, Party::PQ, Party::OTH], parties);
+        assert_eq!("Marie Tremblay", polls[0].name());
+        assert_eq!(Party::CAQ, ridings(polls)[0].winner());
+    }
+
+    #[test]
+    fn unopposed_margin() {
+        let r = riding(&[(Party::LIB, "Red", 600)]);
+
+        assert_eq!(Party::LIB, r.winner());
+        assert_eq!(None, r.runner_up());
+        assert_eq!(600, r.vote_margin());
+        assert!((r.victory_margin() - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn excluding_every_candidate() {
+        let polls = vec![
+            poll("Ajax", Party::CON, "Blue", 500),
+            poll("Ajax", Party::LIB, "Red", 600),
+            poll("Beauce", Party::CON, "Bleu", 700),
+        ];
+
+        let kept = without_parties(polls.clone(), &[Party::LIB]).unwrap();
+        assert_eq!(2, kept.len());
+
+        let err = without_parties(polls, &[Party::CON]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        assert!(err.to_string().contains("Beauce"));
+    }
+
+    #[test]
+    fn independents_dont_win_together() {
+        let r = riding(&[
+            (Party::IND, "One", 375),
+            (Party::IND, "Two", 375),
+            (Party::LIB, "Red", 600),
+        ]);
+
+        assert_eq!(Party::LIB, r.winner());
+        assert_eq!(Some(Party::IND), r.runner_up());
+        assert_eq!(225, r.vote_margin());
+        assert_eq!(3, r.placings().len());
+        assert!((r.victory_margin() - 225.0 / 1350.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn only_one_independent_wins() {
+        let r = riding(&[
+            (Party::IND, "One", 700),
+            (Party::IND, "Two", 100),
+            (Party::LIB, "Red", 600),
+        ]);
+        let (party, victor) = r.victor();
+
+        assert_eq!((&Party::IND, "One"), (party, victor.last_name.as_str()));
+        assert_eq!(3, r.individuals().count());
+        assert!(!r.is_victor(&Party::IND, &candidate("Two", 100)));
+    }
+
+    #[test]
+    fn small_polls() {
+        let mut tiny = poll("Ajax", Party::CON, "Blue", 3);
+        tiny.number = "2".to_string();
+        let polls = vec![
+            poll("Ajax", Party::CON, "Blue", 14),
+            poll("Ajax", Party::LIB, "Red", 6),
+            tiny,
+        ];
+
+        let suppress = SmallPolls {
+            mode: privacy::Mode::Suppress,
+            size: 10,
+        };
+        let kept = suppress.apply(polls.clone());
+        assert_eq!(2, kept.len());
+        assert!(kept.iter().all(|p| p.number == "1"));
+
+        let round = SmallPolls {
+            mode: privacy::Mode::Round,
+            size: 5,
+        };
+        let votes: Vec<_> = round.apply(polls).iter().map(|p| p.votes).collect();
+        assert_eq!(vec![15, 5, 5], votes);
+    }
+
+    #[test]
+    fn bundled_aliases() {
+        let extra = vec![vec!["Old Name".to_string(), "New Name".to_string()]];
+        let aliases = Aliases::new(&extra);
+
+        assert!(aliases.same("Western Arctic", "Northwest Territories"));
+        assert!(aliases.same("Clarington--Scugog--Uxbridge", "Durham"));
+        assert!(aliases.same("Old Name", "New Name"));
+        assert!(!aliases.same("Durham", "Northwest Territories"));
+    }
 }
@@ -0,0 +1,631 @@
+//! Where analysis results are sent once they've been computed.
+
+use crate::cache::Recorder;
+use crate::format::{self, Format};
+use crate::lang::Translation;
+use crate::{fold, tidy};
+use clap::crate_version;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind, Write};
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A destination for rendered results.
+pub trait OutputSink {
+    /// Deliver one complete, rendered result.
+    fn write(&mut self, body: &str) -> Result<(), Error>;
+
+    /// Note the media type of what will be written, for sinks that announce
+    /// it.
+    fn content_type(&mut self, _: &'static str) {}
+}
+
+/// Renders results and hands them to a sink.
+pub struct Output {
+    sink: Box<dyn OutputSink>,
+    tidy: bool,
+    format: Format,
+    colour: bool,
+    envelope: Option<Envelope>,
+    translation: Option<Translation>,
+    /// The field to sort lists by, and whether to sort them descending.
+    sort: Option<(String, bool)>,
+    top: Option<usize>,
+    /// Only list the ridings whose names match this.
+    riding_filter: Option<String>,
+    riding_regex: Option<Regex>,
+    candidate_regex: Option<Regex>,
+}
+
+/// What a result was computed from and when, so that an archived result
+/// still says where it came from.
+#[derive(Serialize)]
+pub struct Envelope {
+    version: &'static str,
+    command: String,
+    year: usize,
+    /// A SHA-256 digest of everything the command read.
+    data: String,
+    generated: String,
+}
+
+impl Envelope {
+    pub fn new(year: usize, data: String) -> Envelope {
+        let args: Vec<_> = std::env::args().skip(1).collect();
+
+        Envelope {
+            version: crate_version!(),
+            command: format!("election {}", args.join(" "))
+                .trim_end()
+                .to_string(),
+            year,
+            data,
+            generated: rfc3339(SystemTime::now()),
+        }
+    }
+}
+
+impl Output {
+    /// Choose a sink from a destination given on the command line:
+    ///
+    /// - `stdout` (the default)
+    /// - `file://PATH`
+    /// - `http://...` or `https://...`, which receives a POST
+    /// - `s3://BUCKET/KEY`, for any S3-compatible object store
+    pub fn new(dest: Option<&str>) -> Result<Output, Error> {
+        let sink: Box<dyn OutputSink> = match dest {
+            None | Some("stdout") | Some("-") => Box::new(Stdout),
+            Some(d) if d.starts_with("file://") => Box::new(File(PathBuf::from(&d[7..]))),
+            Some(d) if d.starts_with("http://") || d.starts_with("https://") => {
+                Box::new(Webhook::new(d))
+            }
+            Some(d) if d.starts_with("s3://") => Box::new(S3::from_env(&d[5..])?),
+            Some(d) => {
+                let msg = format!("Unknown output destination: {}", d);
+                return Err(Error::new(ErrorKind::InvalidInput, msg));
+            }
+        };
+
+        Ok(Output {
+            sink,
+            tidy: false,
+            format: Format::Json,
+            colour: false,
+            envelope: None,
+            translation: None,
+            sort: None,
+            top: None,
+            riding_filter: None,
+            riding_regex: None,
+            candidate_regex: None,
+        })
+    }
+
+    /// Keep a copy of everything emitted at the given cache location.
+    pub fn cached(self, path: &Path) -> Output {
+        Output {
+            sink: Box::new(Recorder::new(self.sink, path)),
+            ..self
+        }
+    }
+
+    /// Reshape everything emitted into long format. See [`crate::tidy`].
+    pub fn tidy(self, tidy: bool) -> Output {
+        Output { tidy, ..self }
+    }
+
+    /// Render everything emitted in the given format.
+    pub fn format(mut self, format: Format) -> Output {
+        self.sink.content_type(format.content_type());
+        Output { format, ..self }
+    }
+
+    /// Colour the parties of tables, and of anything else drawn for the
+    /// terminal.
+    pub fn colour(self, colour: bool) -> Output {
+        Output { colour, ..self }
+    }
+
+    /// Whether to colour what's drawn for the terminal.
+    pub fn coloured(&self) -> bool {
+        self.colour
+    }
+
+    /// Wrap everything emitted as the `results` of an object whose `meta` is
+    /// the given envelope.
+    pub fn envelope(self, envelope: Envelope) -> Output {
+        Output {
+            envelope: Some(envelope),
+            ..self
+        }
+    }
+
+    /// Translate everything emitted in a format meant for reading. JSON and
+    /// the like are left as they are, for the programs that read them.
+    pub fn translate(self, translation: Translation) -> Output {
+        Output {
+            translation: Some(translation),
+            ..self
+        }
+    }
+
+    /// Sort the lists of records emitted by the given field, which can be a
+    /// dotted path to one nested within each record. Lists nested within a
+    /// result, like the parties of `totals`, are sorted too.
+    pub fn sort_by(self, field: Option<String>, desc: bool) -> Output {
+        Output {
+            sort: field.map(|f| (f, desc)),
+            ..self
+        }
+    }
+
+    /// Cut the outermost lists of records emitted down to their first few
+    /// entries. Lists within those records are left whole.
+    pub fn top(self, top: Option<usize>) -> Output {
+        Output { top, ..self }
+    }
+
+    /// Only list the ridings whose names loosely match the given query. See
+    /// [`matches`].
+    pub fn riding_filter(self, riding_filter: Option<String>) -> Output {
+        Output {
+            riding_filter,
+            ..self
+        }
+    }
+
+    /// Only list the ridings, and the candidates, whose names match the given
+    /// patterns.
+    pub fn regexes(self, ridings: Option<Regex>, candidates: Option<Regex>) -> Output {
+        Output {
+            riding_regex: ridings,
+            candidate_regex: candidates,
+            ..self
+        }
+    }
+
+    /// Send an already-rendered result on its way.
+    pub fn write(&mut self, body: &str) -> Result<(), Error> {
+        self.sink.write(body)
+    }
+
+    /// Send a document that isn't a result, like a chart or a web page, on its
+    /// way as it is.
+    pub fn document(&mut self, content_type: &'static str, body: &str) -> Result<(), Error> {
+        self.sink.content_type(content_type);
+        self.sink.write(body.trim_end_matches('\n'))
+    }
+
+    /// Render a result and send it on its way.
+    pub fn emit<T: Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let mut value = serde_json::to_value(value)?;
+        if self.filtered() || self.sort.is_some() || self.top.is_some() {
+            value = self.select(value)?;
+        }
+        if self.tidy {
+            value = tidy::reshape(value);
+        }
+        if let (Some(t), true) = (&self.translation, self.format.is_readable()) {
+            value = t.apply(format::tabular(value));
+        }
+        if let Some(envelope) = &self.envelope {
+            value = serde_json::json!({ "meta": envelope, "results": value });
+        }
+
+        let body = self.format.render(value, self.colour)?;
+        self.sink.write(&body)
+    }
+}
+
+impl Output {
+    /// A result filtered, sorted and cut short as asked for.
+    fn select(&self, value: Value) -> Result<Value, Error> {
+        let filters = self.filters();
+
+        if filters.is_empty() && self.sort.is_none() && self.top.is_none() {
+            return Ok(value);
+        }
+
+        let mut seen = Seen::default();
+        let value = self.arrange(value, &filters, true, &mut seen);
+
+        if seen.lists.not() {
+            let msg = "Filters, --sort-by and --top only apply to results with lists in them";
+            return Err(Error::new(ErrorKind::InvalidInput, msg));
+        }
+        if let Some(f) = filters
+            .iter()
+            .find(|f| seen.records && seen.filtered.contains(&f.flag).not())
+        {
+            let msg = format!(
+                "{} only applies to results with a {} field",
+                f.flag, f.field
+            );
+            return Err(Error::new(ErrorKind::InvalidInput, msg));
+        }
+        if let (Some((field, _)), true, false) = (&self.sort, seen.records, seen.sorted) {
+            let msg = format!("No field named {} to sort by", field);
+            return Err(Error::new(ErrorKind::InvalidInput, msg));
+        }
+
+        Ok(value)
+    }
+
+    fn filtered(&self) -> bool {
+        self.riding_filter.is_some()
+            || self.riding_regex.is_some()
+            || self.candidate_regex.is_some()
+    }
+
+    /// The filters asked for, each applying to any list with its field.
+    fn filters(&self) -> Vec<Filter<'_>> {
+        let mut filters = Vec::new();
+
+        if let Some(query) = &self.riding_filter {
+            filters.push(Filter {
+                field: "riding",
+                flag: "--riding-filter",
+                keep: Box::new(move |n| matches(n, query)),
+            });
+        }
+        if let Some(regex) = &self.riding_regex {
+            filters.push(Filter {
+                field: "riding",
+                flag: "--riding-regex",
+                keep: Box::new(move |n| regex.is_match(n)),
+            });
+        }
+        if let Some(regex) = &self.candidate_regex {
+            filters.push(Filter {
+                field: "name",
+                flag: "--candidate-regex",
+                keep: Box::new(move |n| regex.is_match(n)),
+            });
+        }
+
+        filters
+    }
+
+    /// Filter and sort every list of records within the value that has the
+    /// fields concerned, and cut the outermost of them short.
+    fn arrange(&self, value: Value, filters: &[Filter], outermost: bool, seen: &mut Seen) -> Value {
+        match value {
+            Value::Array(mut items) if items.iter().all(Value::is_object) => {
+                seen.lists = true;
+                seen.records |= items.is_empty().not();
+
+                for filter in filters {
+                    if items.iter().any(|i| i.get(filter.field).is_some()) {
+                        seen.filtered.push(filter.flag);
+                        items.retain(|i| {
+                            i.get(filter.field)
+                                .and_then(Value::as_str)
+                                .is_some_and(&filter.keep)
+                        });
+                    }
+                }
+
+                if let Some((field, desc)) = &self.sort {
+                    let pointer = format!("/{}", field.replace('.', "/"));
+
+                    if items.iter().any(|i| i.pointer(&pointer).is_some()) {
+                        seen.sorted = true;
+                        items.sort_by(|a, b| {
+                            let ordering = compare(a.pointer(&pointer), b.pointer(&pointer));
+                            if *desc {
+                                ordering.reverse()
+                            } else {
+                                ordering
+                            }
+                        });
+                    }
+                }
+
+                if let (Some(top), true) = (self.top, outermost) {
+                    items.truncate(top);
+                }
+
+                let items = items
+                    .into_iter()
+                    .map(|i| self.arrange(i, filters, false, seen))
+                    .collect();
+                Value::Array(items)
+            }
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|i| self.arrange(i, filters, outermost, seen))
+                    .collect(),
+            ),
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| (k, self.arrange(v, filters, outermost, seen)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+}
+
+/// A test on the text of one field of each record in a list.
+struct Filter<'a> {
+    field: &'static str,
+    /// The flag that asked for it.
+    flag: &'static str,
+    keep: Box<dyn Fn(&str) -> bool + 'a>,
+}
+
+/// What turned up while arranging a result.
+#[derive(Default)]
+struct Seen {
+    /// Any list of records, even an empty one.
+    lists: bool,
+    /// Any record within such a list.
+    records: bool,
+    /// The flags of the filters that found a list with their field.
+    filtered: Vec<&'static str>,
+    /// Any list that had the field to sort by.
+    sorted: bool,
+}
+
+/// Does a riding's name loosely match the query? Accents, case, dashes and
+/// punctuation are all ignored, and each word of the query need only appear
+/// somewhere in the name, so that "trois rivieres" finds "Trois-Rivières" and
+/// "vancouv" finds every riding of Vancouver.
+fn matches(name: &str, query: &str) -> bool {
+    let squash = |s: &str| -> String { fold(s).chars().filter(|c| c.is_alphanumeric()).collect() };
+    let name = squash(name);
+
+    query
+        .split_whitespace()
+        .map(squash)
+        .all(|word| name.contains(&word))
+}
+
+/// Numbers by value and text alphabetically, with anything missing last.
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.total_cmp(&b)
+        }
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(Value::Null) | None, Some(Value::Null) | None) => Ordering::Equal,
+        (Some(Value::Null) | None, _) => Ordering::Greater,
+        (_, Some(Value::Null) | None) => Ordering::Less,
+        _ => Ordering::Equal,
+    }
+}
+
+/// Print to the terminal.
+pub struct Stdout;
+
+impl OutputSink for Stdout {
+    fn write(&mut self, body: &str) -> Result<(), Error> {
+        let stdout = std::io::stdout();
+        let mut handle = stdout.lock();
+        writeln!(handle, "{}", body)
+    }
+}
+
+/// Overwrite a local file. The result is written alongside it first and then
+/// moved into place, so that the file is never seen half-written.
+pub struct File(PathBuf);
+
+impl OutputSink for File {
+    fn write(&mut self, body: &str) -> Result<(), Error> {
+        let part = PathBuf::from(format!("{}.part", self.0.display()));
+        std::fs::write(&part, format!("{}\n", body))?;
+        std::fs::rename(&part, &self.0)
+    }
+}
+
+/// POST to some HTTP endpoint.
+pub struct Webhook {
+    url: String,
+    content_type: &'static str,
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Webhook {
+        Webhook {
+            url: url.to_string(),
+            content_type: "application/json",
+        }
+    }
+}
+
+impl OutputSink for Webhook {
+    fn content_type(&mut self, content_type: &'static str) {
+        self.content_type = content_type;
+    }
+
+    fn write(&mut self, body: &str) -> Result<(), Error> {
+        ureq::post(&self.url)
+            .set("Content-Type", self.content_type)
+            .send_string(body)
+            .map_err(Error::other)?;
+
+        Ok(())
+    }
+}
+
+/// PUT into a bucket of an S3-compatible object store.
+///
+/// The endpoint and credentials come from the usual environment variables:
+/// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, `AWS_REGION` (default
+/// `us-east-1`), and `ELECTION_S3_ENDPOINT` for stores other than AWS itself.
+pub struct S3 {
+    endpoint: String,
+    region: String,
+    bucket: String,
+    key: String,
+    access_key: String,
+    secret_key: String,
+    content_type: &'static str,
+}
+
+impl S3 {
+    fn from_env(location: &str) -> Result<S3, Error> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| {
+                let msg = format!("{} must be set to write to S3", name);
+                Error::new(ErrorKind::InvalidInput, msg)
+            })
+        };
+
+        let (bucket, key) = location
+            .split_once('/')
+            .filter(|(b, k)| !b.is_empty() && !k.is_empty())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected s3://BUCKET/KEY"))?;
+        let region = std::env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let endpoint = std::env::var("ELECTION_S3_ENDPOINT")
+            .unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", region));
+
+        Ok(S3 {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            region,
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            access_key: var("AWS_ACCESS_KEY_ID")?,
+            secret_key: var("AWS_SECRET_ACCESS_KEY")?,
+            content_type: "application/json",
+        })
+    }
+}
+
+impl OutputSink for S3 {
+    fn content_type(&mut self, content_type: &'static str) {
+        self.content_type = content_type;
+    }
+
+    /// A path-style PUT, signed with AWS Signature Version 4.
+    fn write(&mut self, body: &str) -> Result<(), Error> {
+        let host = self
+            .endpoint
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or_default();
+        let path = format!("/{}/{}", uri_encode(&self.bucket), uri_encode(&self.key));
+        let (date, stamp) = timestamp(SystemTime::now());
+        let payload = hex(&Sha256::digest(body.as_bytes()));
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+        let canonical = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            path, host, payload, stamp, signed_headers, payload
+        );
+        let to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            stamp,
+            scope,
+            hex(&Sha256::digest(canonical.as_bytes()))
+        );
+
+        let key = [date.as_str(), &self.region, "s3", "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.secret_key).into_bytes(),
+                |k, part| hmac(&k, part.as_bytes()),
+            );
+        let signature = hex(&hmac(&key, to_sign.as_bytes()));
+        let auth = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        ureq::put(&format!("{}{}", self.endpoint, path))
+            .set("Authorization", &auth)
+            .set("Content-Type", self.content_type)
+            .set("x-amz-content-sha256", &payload)
+            .set("x-amz-date", &stamp)
+            .send_string(body)
+            .map_err(Error::other)?;
+
+        Ok(())
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length, so this can't fail.
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent-encode everything but the characters S3 leaves alone.
+fn uri_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// The `YYYYMMDD` date and `YYYYMMDD'T'HHMMSS'Z'` timestamp that request
+/// signing expects, in UTC.
+fn timestamp(time: SystemTime) -> (String, String) {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    let date = format!("{:04}{:02}{:02}", y, m, d);
+    let stamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    );
+
+    (date, stamp)
+}
+
+/// A time as `YYYY-MM-DD'T'HH:MM:SS'Z'`, in UTC.
+fn rfc3339(time: SystemTime) -> String {
+    let (date, stamp) = timestamp(time);
+
+    format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &date[0..4],
+        &date[4..6],
+        &date[6..8],
+        &stamp[9..11],
+        &stamp[11..13],
+        &stamp[13..15]
+    )
+}
+
+/// Convert days since the Unix epoch into a (year, month, day) triple.
+/// See Howard Hinnant's `chrono`-compatible date algorithms.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+
+    (y, m, d)
+}
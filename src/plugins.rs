@@ -0,0 +1,109 @@
+//! External analyses. Running `election foo ARGS` when `foo` isn't a built-in
+//! command looks for an executable named `election-foo` on the `PATH` and runs
+//! it with `ARGS`. The plugin receives every available year's results on
+//! stdin, as newline-delimited JSON with one candidate per line. Options like
+//! `--data-dir` and `--corrections` given before `foo` shape those results;
+//! everything after it is the plugin's own.
+
+use crate::config::Settings;
+use crate::{available_years, load, ridings, Party};
+use serde::Serialize;
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// One candidate's result, as handed to plugins.
+#[derive(Serialize)]
+struct Record {
+    year: usize,
+    district: u32,
+    riding: String,
+    electors: usize,
+    rejected: usize,
+    party: Party,
+    name: String,
+    last_name: String,
+    first_name: String,
+    votes: usize,
+    incumbent: bool,
+    elected: bool,
+}
+
+/// The plugin executable for the given command, if there is one.
+pub fn find(command: &str) -> Option<PathBuf> {
+    if command.starts_with('-') {
+        return None;
+    }
+
+    let name = format!("election-{}", command);
+
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&name))
+        .find(|path| is_executable(path))
+}
+
+/// Whether the file can be run: a regular file with an executable bit set.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Whether the file can be run. Only Unix has mode bits to go by.
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Run a plugin, feeding it the data on stdin.
+pub fn dispatch(plugin: &Path, args: &[String], settings: &Settings) -> Result<(), Error> {
+    let mut child = Command::new(plugin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        match feed(settings, &mut stdin) {
+            // The plugin doesn't have to read everything it's given.
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+
+    let status = child.wait()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        let msg = format!("{} failed: {}", plugin.display(), status);
+        Err(Error::other(msg))
+    }
+}
+
+fn feed<W: Write>(settings: &Settings, w: &mut W) -> Result<(), Error> {
+    for year in available_years(settings) {
+        for riding in ridings(load(settings, year)?) {
+            for (party, c) in riding.individuals() {
+                let record = Record {
+                    year,
+                    district: riding.district,
+                    riding: riding.name.clone(),
+                    electors: riding.electors,
+                    rejected: riding.rejected,
+                    party: party.clone(),
+                    name: c.name(),
+                    last_name: c.last_name.clone(),
+                    first_name: c.first_name.clone(),
+                    votes: c.votes,
+                    incumbent: c.incumbent,
+                    elected: c.elected,
+                };
+                serde_json::to_writer(&mut *w, &record)?;
+                writeln!(w)?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! Charts drawn as PNG images, for those without an SVG toolchain. Only built
+//! with the `png` feature.
+
+use crate::config::Settings;
+use crate::format::label;
+use crate::{available_years, load, margins_of, totals_of, Party, Poll};
+use itertools::Itertools;
+use plotters::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Error;
+use std::ops::Not;
+use std::path::Path;
+
+/// The size of every image, in pixels.
+const SIZE: (u32, u32) = (960, 600);
+
+/// How wide each bin of margins is, in percentage points.
+const BIN: f64 = 5.0;
+
+/// A histogram of how many ridings were won by how much.
+pub fn margins(title: &str, polls: Vec<Poll>, out: &Path) -> Result<(), Error> {
+    let bins: Vec<u32> = margins_of(polls, false)
+        .iter()
+        .map(|m| (m.margin * 100.0 / BIN) as u32)
+        .collect();
+    let count = bins.iter().max().map_or(1, |b| b + 1);
+    let tallest = bins.iter().counts().values().max().copied().unwrap_or(0) as u32;
+
+    let root = BitMapBackend::new(out, SIZE).into_drawing_area();
+    root.fill(&WHITE).map_err(fail)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 28))
+        .margin(20)
+        .x_label_area_size(50)
+        .y_label_area_size(60)
+        .build_cartesian_2d((0..count).into_segmented(), 0..tallest + 1)
+        .map_err(fail)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_desc("Margin of victory (points)")
+        .y_desc("Ridings")
+        .x_label_formatter(&|v| match v {
+            SegmentValue::Exact(b) | SegmentValue::CenterOf(b) => {
+                format!("{}", *b as f64 * BIN)
+            }
+            SegmentValue::Last => String::new(),
+        })
+        .draw()
+        .map_err(fail)?;
+
+    chart
+        .draw_series(
+            Histogram::vertical(&chart)
+                .style(RGBColor(0x44, 0x66, 0x99).filled())
+                .margin(2)
+                .data(bins.into_iter().map(|b| (b, 1))),
+        )
+        .map_err(fail)?;
+
+    root.present().map_err(fail)
+}
+
+/// The change in each major party's national share of the vote between two
+/// elections.
+pub fn swing(settings: &Settings, before: usize, after: usize, out: &Path) -> Result<(), Error> {
+    let shares = |year| -> Result<BTreeMap<Party, f64>, Error> {
+        Ok(totals_of(load(settings, year)?)
+            .parties
+            .into_iter()
+            .filter(|v| v.party.is_fringe().not())
+            .map(|v| (v.party, v.ratio))
+            .collect())
+    };
+    let (b, a) = (shares(before)?, shares(after)?);
+    let swings: Vec<(Party, f64)> = a
+        .keys()
+        .chain(b.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|p| {
+            let change = a.get(p).unwrap_or(&0.0) - b.get(p).unwrap_or(&0.0);
+            (p.clone(), change * 100.0)
+        })
+        .collect();
+    let furthest = swings
+        .iter()
+        .map(|(_, s)| s.abs())
+        .fold(1.0, f64::max)
+        .ceil();
+
+    let root = BitMapBackend::new(out, SIZE).into_drawing_area();
+    root.fill(&WHITE).map_err(fail)?;
+
+    let title = format!("Change in share of the vote, {} to {}", before, after);
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 28))
+        .margin(20)
+        .x_label_area_size(50)
+        .y_label_area_size(60)
+        .build_cartesian_2d(
+            (0..swings.len() as u32).into_segmented(),
+            -furthest..furthest,
+        )
+        .map_err(fail)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .y_desc("Points")
+        .x_label_formatter(&|v| match v {
+            SegmentValue::Exact(i) | SegmentValue::CenterOf(i) => swings
+                .get(*i as usize)
+                .map(|(p, _)| label(p))
+                .unwrap_or_default(),
+            SegmentValue::Last => String::new(),
+        })
+        .draw()
+        .map_err(fail)?;
+
+    chart
+        .draw_series(swings.iter().enumerate().map(|(i, (party, s))| {
+            let i = i as u32;
+            let mut bar = Rectangle::new(
+                [
+                    (SegmentValue::Exact(i), 0.0),
+                    (SegmentValue::Exact(i + 1), *s),
+                ],
+                colour(party).filled(),
+            );
+            bar.set_margin(0, 0, 8, 8);
+            bar
+        }))
+        .map_err(fail)?;
+
+    root.present().map_err(fail)
+}
+
+/// The seats each major party won in every election there's data for.
+pub fn trend(settings: &Settings, out: &Path) -> Result<(), Error> {
+    let mut seats: BTreeMap<Party, Vec<(u32, u32)>> = BTreeMap::new();
+    let years: Vec<u32> = available_years(settings).map(|y| y as u32).collect();
+
+    for year in years.iter() {
+        for v in totals_of(load(settings, *year as usize)?).parties {
+            if v.party.is_fringe().not() {
+                seats
+                    .entry(v.party)
+                    .or_default()
+                    .push((*year, v.seats as u32));
+            }
+        }
+    }
+
+    let most = seats.values().flatten().map(|(_, s)| *s).max().unwrap_or(0);
+    let (first, last) = match (years.first(), years.last()) {
+        (Some(f), Some(l)) => (*f, *l),
+        _ => return Err(Error::other("No elections to draw")),
+    };
+
+    let root = BitMapBackend::new(out, SIZE).into_drawing_area();
+    root.fill(&WHITE).map_err(fail)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption("Seats won", ("sans-serif", 28))
+        .margin(20)
+        .x_label_area_size(50)
+        .y_label_area_size(60)
+        .build_cartesian_2d(first..last.max(first + 1), 0..most + 10)
+        .map_err(fail)?;
+
+    chart
+        .configure_mesh()
+        .x_labels(years.len())
+        .x_label_formatter(&|y| y.to_string())
+        .y_desc("Seats")
+        .draw()
+        .map_err(fail)?;
+
+    for (party, points) in seats {
+        let c = colour(&party);
+
+        chart
+            .draw_series(LineSeries::new(points.clone(), c.stroke_width(3)))
+            .map_err(fail)?
+            .label(label(&party))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], c.stroke_width(3)));
+        chart
+            .draw_series(points.into_iter().map(|p| Circle::new(p, 4, c.filled())))
+            .map_err(fail)?;
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(fail)?;
+
+    root.present().map_err(fail)
+}
+
+fn colour(party: &Party) -> RGBColor {
+    let (r, g, b) = party.rgb();
+    RGBColor(r, g, b)
+}
+
+fn fail<E: std::fmt::Display>(e: E) -> Error {
+    Error::other(e.to_string())
+}
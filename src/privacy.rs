@@ -0,0 +1,62 @@
+//! Keeping tiny polls from singling out their voters. A poll of a handful of
+//! ballots can all but say how a given household voted, which matters to
+//! anyone publishing data derived from poll-level results. With
+//! `--small-polls`, poll-level output either leaves out the polls with fewer
+//! than `--small-poll-size` votes, or rounds every count to a multiple of it.
+//!
+//! Only output that keeps polls apart is affected: `by-poll` and the
+//! `polls.parquet` of `export parquet`. Riding totals are public anyway.
+
+use crate::Poll;
+use clap::ArgEnum;
+use itertools::Itertools;
+
+/// How small polls are kept from standing out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Mode {
+    /// Leave out polls with fewer votes than the size.
+    Suppress,
+    /// Round every count to the nearest multiple of the size.
+    Round,
+}
+
+/// A [`Mode`] and the poll size it applies to.
+#[derive(Debug, Clone, Copy)]
+pub struct SmallPolls {
+    pub mode: Mode,
+    pub size: usize,
+}
+
+impl SmallPolls {
+    /// The given polls, unfused, with the small ones dealt with.
+    pub fn apply(&self, polls: Vec<Poll>) -> Vec<Poll> {
+        match self.mode {
+            Mode::Suppress => polls
+                .into_iter()
+                .sorted_by(|a, b| (a.district, &a.number).cmp(&(b.district, &b.number)))
+                .group_by(|p| (p.district, p.number.clone()))
+                .into_iter()
+                .map(|(_, group)| group.collect::<Vec<_>>())
+                .filter(|group| group.iter().map(|p| p.votes).sum::<usize>() >= self.size)
+                .flatten()
+                .collect(),
+            Mode::Round => polls.into_iter().map(|p| self.round(p)).collect(),
+        }
+    }
+
+    fn round(&self, mut poll: Poll) -> Poll {
+        let round = |n: usize| match self.size {
+            0 => n,
+            size => (n + size / 2) / size * size,
+        };
+
+        poll.votes = round(poll.votes);
+        poll.electors = round(poll.electors);
+        poll.rejected = round(poll.rejected);
+        poll.ballots.ordinary = round(poll.ballots.ordinary);
+        poll.ballots.advance = round(poll.ballots.advance);
+        poll.ballots.mobile = round(poll.ballots.mobile);
+        poll.ballots.special = round(poll.ballots.special);
+        poll
+    }
+}
@@ -0,0 +1,120 @@
+//! Results of elections held on older boundaries, transposed onto newer ones,
+//! so that ridings from either side of a redistribution can be compared.
+//!
+//! The transposition is read from a CSV whose rows give an old district
+//! number (`from`), a new one (`to`), and the `share` of the old riding's
+//! votes cast in what is now the new riding. Elections Canada publishes the
+//! figures this is drawn from as its "transposition of votes" for each
+//! representation order.
+
+use crate::{Ballots, Candidate, Party, Riding};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Error;
+use std::ops::Not;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct Row {
+    from: u32,
+    to: u32,
+    share: f64,
+}
+
+/// How the old ridings map onto the new.
+pub struct Redistribution {
+    rows: Vec<Row>,
+}
+
+/// A candidate's apportioned result, before rounding.
+#[derive(Default)]
+struct Share {
+    votes: f64,
+    ordinary: f64,
+    advance: f64,
+    mobile: f64,
+    special: f64,
+}
+
+impl Share {
+    fn add(&mut self, c: &Candidate, share: f64) {
+        self.votes += c.votes as f64 * share;
+        self.ordinary += c.ballots.ordinary as f64 * share;
+        self.advance += c.ballots.advance as f64 * share;
+        self.mobile += c.ballots.mobile as f64 * share;
+        self.special += c.ballots.special as f64 * share;
+    }
+
+    /// A stand-in for all the candidates of a party whose votes now fall
+    /// within one riding. It represents no one person, so it has no name.
+    fn candidate(&self) -> Candidate {
+        Candidate {
+            last_name: String::new(),
+            first_name: String::new(),
+            middle_name: String::new(),
+            votes: self.votes.round() as usize,
+            ballots: Ballots {
+                ordinary: self.ordinary.round() as usize,
+                advance: self.advance.round() as usize,
+                mobile: self.mobile.round() as usize,
+                special: self.special.round() as usize,
+            },
+            incumbent: false,
+            elected: false,
+        }
+    }
+}
+
+impl Redistribution {
+    pub fn load(path: &Path) -> Result<Redistribution, Error> {
+        let rows = csv::Reader::from_path(path)?
+            .deserialize()
+            .collect::<Result<_, _>>()?;
+
+        Ok(Redistribution { rows })
+    }
+
+    /// Apportion the results of the old ridings onto the new, named after
+    /// their counterparts in `new`. New ridings that the transposition says
+    /// nothing about are left out.
+    pub fn transpose(&self, old: &[Riding], new: &[Riding]) -> Vec<Riding> {
+        let old: HashMap<u32, &Riding> = old.iter().map(|r| (r.district, r)).collect();
+
+        new.iter()
+            .filter_map(|n| {
+                let mut shares: HashMap<Party, Share> = HashMap::new();
+                let mut electors = 0.0;
+                let mut rejected = 0.0;
+
+                for row in self.rows.iter().filter(|r| r.to == n.district) {
+                    if let Some(o) = old.get(&row.from) {
+                        electors += o.electors as f64 * row.share;
+                        rejected += o.rejected as f64 * row.share;
+
+                        for (party, c) in o.candidates.iter() {
+                            shares.entry(party.clone()).or_default().add(c, row.share);
+                        }
+                    }
+                }
+
+                let candidates: HashMap<Party, Candidate> = shares
+                    .into_iter()
+                    .map(|(p, s)| (p, s.candidate()))
+                    .collect();
+
+                candidates.is_empty().not().then(|| Riding {
+                    name: n.name.clone(),
+                    district: n.district,
+                    electors: electors.round() as usize,
+                    rejected: rejected.round() as usize,
+                    unaffiliated: candidates
+                        .iter()
+                        .filter(|(p, _)| p.is_unaffiliated())
+                        .map(|(p, c)| (p.clone(), c.clone()))
+                        .collect(),
+                    candidates,
+                })
+            })
+            .collect()
+    }
+}
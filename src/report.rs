@@ -0,0 +1,103 @@
+//! `election report [--year YEAR]`: a single HTML page of an election's
+//! headline results, for sharing with people who'd rather not read JSON.
+//! Everything it needs, styling and table sorting included, is inline. Like
+//! any result, it goes wherever `--output` or `--sink` say.
+
+use crate::format::{self, label};
+use crate::output::Output;
+use crate::{margins_of, totals_of, Poll, CLOSE_MARGIN};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::io::Error;
+
+const STYLE: &str = "
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 0.25em 0.75em; border-bottom: 1px solid #ddd; text-align: left; }
+th { cursor: pointer; user-select: none; }
+.num { text-align: right; }
+";
+
+/// Sort a table by the column whose header was clicked, flipping between
+/// ascending and descending on each click.
+const SCRIPT: &str = "
+document.querySelectorAll('th').forEach(th => th.addEventListener('click', () => {
+  const table = th.closest('table');
+  const body = table.querySelector('tbody');
+  const i = Array.from(th.parentNode.children).indexOf(th);
+  const num = th.classList.contains('num');
+  const asc = th.dataset.asc !== 'true';
+  const key = tr => {
+    const text = tr.children[i].textContent;
+    return num ? parseFloat(text.replace(/[,%]/g, '')) || 0 : text;
+  };
+  const rows = Array.from(body.rows).sort((a, b) => {
+    const [x, y] = [key(a), key(b)];
+    return (x < y ? -1 : x > y ? 1 : 0) * (asc ? 1 : -1);
+  });
+  table.querySelectorAll('th').forEach(h => delete h.dataset.asc);
+  th.dataset.asc = asc;
+  rows.forEach(tr => body.appendChild(tr));
+}));
+";
+
+/// Build the report of the year's polls, already fused.
+pub fn report(year: usize, polls: Vec<Poll>, out: &mut Output) -> Result<(), Error> {
+    let mut totals = totals_of(polls.clone());
+    totals.parties.sort_by_key(|p| Reverse((p.seats, p.votes)));
+    let margins = margins_of(polls, false);
+    let closest: Vec<_> = margins.iter().filter(|m| m.margin < CLOSE_MARGIN).collect();
+
+    let government = match &totals.government {
+        Some(g) if g.majority => format!(
+            "A majority government: {}, with {} seats.",
+            label(&g.party),
+            g.seats
+        ),
+        Some(g) => format!(
+            "A minority government: {}, with {} seats, {} short of a majority.",
+            label(&g.party),
+            g.seats,
+            g.shortfall
+        ),
+        None => "No seats were decided.".to_string(),
+    };
+
+    let page = format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>The {year} Federal Election</title>
+<style>{style}</style>
+</head>
+<body>
+<h1>The {year} Federal Election</h1>
+<p>{government}</p>
+<h2>Votes and Seats</h2>
+{parties}
+<h2>Closest Races</h2>
+<p>Ridings won by less than {close}% of the vote.</p>
+{closest}
+<h2>Every Riding</h2>
+{margins}
+<script>{script}</script>
+</body>
+</html>
+",
+        year = year,
+        style = STYLE,
+        government = government,
+        parties = table(&totals.parties)?,
+        close = CLOSE_MARGIN * 100.0,
+        closest = table(&closest)?,
+        margins = table(&margins)?,
+        script = SCRIPT,
+    );
+
+    out.document("text/html", &page)
+}
+
+fn table<T: Serialize>(value: &T) -> Result<String, Error> {
+    Ok(format::html(serde_json::to_value(value)?))
+}
@@ -0,0 +1,105 @@
+//! Small, anonymized extracts of a year's data, for demos and bug reports.
+//!
+//! The year's polls are read however they're kept, zipped or not and even as
+//! summary tables, and a random handful of ridings are written back out as
+//! one poll-by-poll file each, in a directory for the year, so that the sample
+//! can stand in for a data directory of its own.
+
+use crate::{read_polls, Poll};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+/// What a sample ended up containing.
+#[derive(Serialize, JsonSchema)]
+pub struct Sample {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub files: Vec<String>,
+}
+
+/// Copy `count` randomly chosen ridings of the year's data in one directory to
+/// the year's directory within another, renaming every candidate. The same
+/// seed always picks the same ridings.
+pub fn extract(
+    from: &Path,
+    to: &Path,
+    year: usize,
+    count: usize,
+    seed: u64,
+) -> Result<Sample, Error> {
+    let mut ridings: BTreeMap<u32, Vec<Poll>> = BTreeMap::new();
+    for poll in read_polls(from)? {
+        ridings.entry(poll.district).or_default().push(poll);
+    }
+
+    let mut districts: Vec<u32> = ridings.keys().copied().collect();
+    shuffle(&mut districts, seed);
+    districts.truncate(count);
+    districts.sort_unstable();
+
+    let dir = to.join(year.to_string());
+    std::fs::create_dir_all(&dir)?;
+
+    let mut files = Vec::new();
+
+    for district in districts {
+        let name = format!("pollbypoll_bureauparbureau{}.csv", district);
+        let polls = ridings.remove(&district).unwrap_or_default();
+        write(anonymize(polls), &dir.join(&name))?;
+        files.push(name);
+    }
+
+    Ok(Sample {
+        from: from.to_path_buf(),
+        to: dir,
+        files,
+    })
+}
+
+/// Rename each candidate to `Candidate N`, where `N` is the order in which
+/// they first appear.
+fn anonymize(polls: Vec<Poll>) -> Vec<Poll> {
+    let mut aliases: HashMap<(String, String), usize> = HashMap::new();
+
+    polls
+        .into_iter()
+        .map(|mut poll| {
+            let next = aliases.len() + 1;
+            let key = (poll.last_name.clone(), poll.first_name.clone());
+            let n = *aliases.entry(key).or_insert(next);
+
+            poll.last_name = "Candidate".to_string();
+            poll.first_name = n.to_string();
+            poll.middle_name = String::new();
+            poll
+        })
+        .collect()
+}
+
+/// Write a riding's polls as Elections Canada lays them out.
+fn write(polls: Vec<Poll>, path: &Path) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    for poll in polls {
+        writer.serialize(poll)?;
+    }
+
+    writer.flush()
+}
+
+/// A Fisher-Yates shuffle driven by a small xorshift generator, which is all
+/// the randomness that picking a sample needs.
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    // Xorshift gets stuck at zero.
+    let mut state = seed ^ 0x9E37_79B9_7F4A_7C15;
+
+    for i in (1..items.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        items.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
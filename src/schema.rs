@@ -0,0 +1,102 @@
+//! `election schema [NAME]`: JSON Schemas of everything the tool outputs, for
+//! validating results and generating typed clients against them.
+//!
+//! Schemas are named after the command that produces them, like `totals`,
+//! `margins` or `validate`. Without a name, every schema is given.
+
+use crate::careers::Career;
+use crate::export::Exported;
+use crate::fetch::Fetched;
+use crate::sample::Sample;
+use crate::validate::Report;
+use crate::{
+    AdvanceRiding, ByElections, CandidateCounts, CandidateRun, CareerStats, CaucusMargins,
+    ClassSummary, ComboVictory, Competitiveness, Coverage, Decisive, Deposits, Fingerprint,
+    Fragmentation, Heartlands, HistogramBin, HistoryEntry, Incumbency, Independents, Inversion,
+    LateFlip, Majorities, PartyResults, PartySwings, PollResult, Quebec, RejectedBallots,
+    RidingProfile, RidingTurnout, SeatBlock, SeatFlip, SpecialBallots, Spoiler, Totals,
+    VictoryMargin, WinnerDiscrepancy,
+};
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+use serde_json::{Map, Value};
+use std::io::{Error, ErrorKind};
+
+/// Each output's name and schema.
+fn schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        ("totals", of::<Totals>()),
+        ("combine", of::<Vec<ComboVictory>>()),
+        ("margins", of::<Vec<VictoryMargin>>()),
+        ("party", of::<Vec<PartyResults>>()),
+        ("candidates", of::<CandidateCounts>()),
+        ("incumbents", of::<Incumbency>()),
+        ("verify", of::<Vec<WinnerDiscrepancy>>()),
+        ("candidate", of::<Vec<CandidateRun>>()),
+        ("careers", of::<Vec<Career>>()),
+        ("streaks", of::<Vec<CareerStats>>()),
+        ("switchers", of::<Vec<Career>>()),
+        ("compare", of::<Vec<SeatFlip>>()),
+        ("swing", of::<Vec<PartySwings>>()),
+        ("history", of::<Vec<HistoryEntry>>()),
+        ("riding", of::<RidingProfile>()),
+        ("quebec", of::<Quebec>()),
+        ("special", of::<SpecialBallots>()),
+        ("advance", of::<Vec<AdvanceRiding>>()),
+        ("by-class", of::<Vec<ClassSummary>>()),
+        ("late-flips", of::<Vec<LateFlip>>()),
+        ("decisive", of::<Decisive>()),
+        ("histogram", of::<Vec<HistogramBin>>()),
+        ("deposits", of::<Deposits>()),
+        ("heartlands", of::<Heartlands>()),
+        ("by-poll", of::<Vec<PollResult>>()),
+        ("poll-turnout", of::<Vec<RidingTurnout>>()),
+        ("rejected", of::<Vec<RejectedBallots>>()),
+        ("competitiveness", of::<Vec<Competitiveness>>()),
+        ("fragmentation", of::<Vec<Fragmentation>>()),
+        ("independents", of::<Independents>()),
+        ("spoilers", of::<Vec<Spoiler>>()),
+        ("coverage", of::<Vec<Coverage>>()),
+        ("fingerprint", of::<Fingerprint>()),
+        ("caucus-margins", of::<Vec<CaucusMargins>>()),
+        ("byelections", of::<ByElections>()),
+        ("inversions", of::<Vec<Inversion>>()),
+        ("hemicycle", of::<Vec<SeatBlock>>()),
+        ("majorities", of::<Majorities>()),
+        ("sample", of::<Sample>()),
+        ("fetch", of::<Fetched>()),
+        ("validate", of::<Report>()),
+        ("export", of::<Exported>()),
+    ]
+}
+
+/// The schema of a type as it's written out, which for some differs from how
+/// it's read.
+fn of<T: JsonSchema>() -> Schema {
+    SchemaSettings::default()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// The schema of the named output, or all of them by name.
+pub fn schema(name: Option<&str>) -> Result<Value, Error> {
+    let schemas = schemas();
+
+    match name {
+        None => {
+            let all: Map<String, Value> = schemas
+                .into_iter()
+                .map(|(name, s)| Ok((name.to_string(), serde_json::to_value(s)?)))
+                .collect::<Result<_, Error>>()?;
+            Ok(Value::Object(all))
+        }
+        Some(wanted) => match schemas.into_iter().find(|(name, _)| *name == wanted) {
+            Some((_, s)) => Ok(serde_json::to_value(s)?),
+            None => {
+                let msg = format!("No output named {}", wanted);
+                Err(Error::new(ErrorKind::InvalidInput, msg))
+            }
+        },
+    }
+}
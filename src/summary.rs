@@ -0,0 +1,96 @@
+//! Elections Canada's riding-level summary tables (the `table_tableau12`
+//! format), as a fallback for when poll-by-poll data isn't available.
+//!
+//! Each candidate's row becomes a single poll, so analyses that need
+//! individual polls, electors or rejected ballots won't find them.
+
+use crate::{Ballots, Party, Poll};
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::{Error, ErrorKind, Read};
+
+#[derive(Deserialize)]
+struct Row {
+    #[serde(rename = "Electoral District Number/Numéro de circonscription")]
+    #[serde(alias = "Numéro de circonscription/Electoral District Number")]
+    district: u32,
+    #[serde(rename = "Electoral District Name/Nom de circonscription")]
+    #[serde(alias = "Nom de circonscription/Electoral District Name")]
+    riding: String,
+    /// The candidate's name followed by their party, as in
+    /// `Justin Trudeau Liberal/Libéral`.
+    #[serde(rename = "Candidate/Candidat")]
+    #[serde(alias = "Candidat/Candidate")]
+    candidate: String,
+    #[serde(rename = "Votes Obtained/Votes obtenus")]
+    #[serde(alias = "Votes obtenus/Votes Obtained")]
+    votes: usize,
+    /// Only given for the winner.
+    #[serde(rename = "Majority/Majorité", default)]
+    #[serde(alias = "Majorité/Majority")]
+    majority: String,
+}
+
+/// Is this the header of a summary table?
+pub fn is_summary(headers: &csv::StringRecord) -> bool {
+    headers
+        .iter()
+        .any(|h| h == "Candidate/Candidat" || h == "Candidat/Candidate")
+}
+
+/// Read a summary table as if it were poll-by-poll data.
+pub fn read<R: Read>(mut reader: csv::Reader<R>) -> Result<Vec<Poll>, Error> {
+    let headers = reader.headers()?.clone();
+
+    reader
+        .records()
+        .filter(|r| r.as_ref().map_or(true, |r| *r != headers))
+        .map(|r| {
+            let row: Row = r?.deserialize(Some(&headers))?;
+            let (first_name, last_name, party) = split_candidate(&row.candidate)?;
+
+            let poll = Poll {
+                district: row.district,
+                // Bilingual ridings are named `English/French` here.
+                riding: row.riding.split('/').next().unwrap_or_default().to_string(),
+                riding_fr: row.riding.split('/').nth(1).unwrap_or_default().to_string(),
+                number: String::new(),
+                station: String::new(),
+                void: false,
+                not_held: false,
+                party,
+                last_name,
+                first_name,
+                middle_name: String::new(),
+                votes: row.votes,
+                incumbent: false,
+                elected: !row.majority.trim().is_empty(),
+                electors: 0,
+                rejected: 0,
+                ballots: Ballots::default(),
+            };
+
+            Ok(poll.classify())
+        })
+        .collect()
+}
+
+/// Separate a candidate's first name, last name and party. The party's
+/// English name is the longest run of trailing words that names a known party.
+fn split_candidate(candidate: &str) -> Result<(String, String, Party), Error> {
+    let english = candidate.split('/').next().unwrap_or_default();
+    let words: Vec<&str> = english.split_whitespace().collect();
+
+    (1..words.len())
+        .find_map(|start| {
+            let label = Value::String(words[start..].join(" "));
+            let party = serde_json::from_value::<Party>(label).ok()?;
+            let first = words[0].to_string();
+            let last = words[1..start].join(" ");
+            Some((first, last, party))
+        })
+        .ok_or_else(|| {
+            let msg = format!("Couldn't find a known party in: {}", candidate);
+            Error::new(ErrorKind::InvalidData, msg)
+        })
+}
@@ -0,0 +1,62 @@
+//! Long-format ("tidy") reshaping of any result, for tools like R or pivot
+//! tables that want one observation per row.
+//!
+//! Every number or boolean in a result becomes its own row, carrying the
+//! identifying fields (the riding, party, year and so on) of every object
+//! enclosing it, the dotted path to the value as its `metric`, and the value
+//! itself.
+
+use serde_json::{Map, Value};
+
+/// Fields that identify an observation even though they're numbers.
+pub const NUMERIC_IDS: &[&str] = &["district", "year"];
+
+/// Flatten a result into rows of identifiers, a `metric` and a `value`.
+pub fn reshape(value: Value) -> Value {
+    let mut rows = Vec::new();
+    walk(value, &Map::new(), "", &mut rows);
+    Value::Array(rows)
+}
+
+fn walk(value: Value, ids: &Map<String, Value>, metric: &str, rows: &mut Vec<Value>) {
+    match value {
+        Value::Object(fields) => {
+            let (own, rest): (Vec<_>, Vec<_>) = fields
+                .into_iter()
+                .partition(|(k, v)| v.is_string() || NUMERIC_IDS.contains(&k.as_str()));
+            let mut ids = ids.clone();
+            ids.extend(own);
+
+            for (k, v) in rest {
+                walk(v, &ids, &join(metric, &k), rows);
+            }
+        }
+        Value::Array(items) => {
+            let scalars = items.iter().all(|v| !v.is_object() && !v.is_array());
+
+            for (i, v) in items.into_iter().enumerate() {
+                if scalars {
+                    walk(v, ids, &join(metric, &i.to_string()), rows);
+                } else {
+                    walk(v, ids, metric, rows);
+                }
+            }
+        }
+        // Strings only ever identify.
+        Value::String(_) => {}
+        value => {
+            let mut row = ids.clone();
+            row.insert("metric".to_string(), Value::String(metric.to_string()));
+            row.insert("value".to_string(), value);
+            rows.push(Value::Object(row));
+        }
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
@@ -0,0 +1,71 @@
+//! Elections Canada's transposed results: an election's votes restated on the
+//! boundaries of the representation order that followed it. They live in
+//! `transposed/YEAR/` within the data directory, and give each party's votes
+//! in each new riding, with no candidates and no polls.
+//!
+//! Each row becomes a single poll for a nameless candidate, so the ridings
+//! they make can be compared directly with those of later elections.
+
+use crate::{Ballots, Party, Poll};
+use serde::Deserialize;
+use std::io::{Error, Read};
+
+#[derive(Deserialize)]
+struct Row {
+    #[serde(rename = "Electoral District Number/Numéro de circonscription")]
+    #[serde(alias = "Numéro de circonscription/Electoral District Number")]
+    district: u32,
+    #[serde(rename = "Electoral District Name/Nom de circonscription")]
+    #[serde(alias = "Nom de circonscription/Electoral District Name")]
+    riding: String,
+    #[serde(rename = "Political Affiliation/Appartenance politique")]
+    #[serde(alias = "Appartenance politique/Political Affiliation")]
+    #[serde(alias = "Party/Parti")]
+    party: Party,
+    #[serde(rename = "Transposed Votes/Votes transposés")]
+    #[serde(alias = "Votes transposés/Transposed Votes")]
+    #[serde(alias = "Votes/Votes")]
+    votes: usize,
+}
+
+/// Is this the header of a table of transposed results?
+pub fn is_transposed(headers: &csv::StringRecord) -> bool {
+    headers.iter().any(|h| {
+        h == "Transposed Votes/Votes transposés" || h == "Votes transposés/Transposed Votes"
+    })
+}
+
+/// Read a table of transposed results as if it were poll-by-poll data.
+pub fn read<R: Read>(mut reader: csv::Reader<R>) -> Result<Vec<Poll>, Error> {
+    let headers = reader.headers()?.clone();
+
+    reader
+        .records()
+        .filter(|r| r.as_ref().map_or(true, |r| *r != headers))
+        .map(|r| {
+            let row: Row = r?.deserialize(Some(&headers))?;
+
+            let poll = Poll {
+                district: row.district,
+                riding: row.riding.split('/').next().unwrap_or_default().to_string(),
+                riding_fr: row.riding.split('/').nth(1).unwrap_or_default().to_string(),
+                number: String::new(),
+                station: String::new(),
+                void: false,
+                not_held: false,
+                party: row.party,
+                last_name: String::new(),
+                first_name: String::new(),
+                middle_name: String::new(),
+                votes: row.votes,
+                incumbent: false,
+                elected: false,
+                electors: 0,
+                rejected: 0,
+                ballots: Ballots::default(),
+            };
+
+            Ok(poll.classify())
+        })
+        .collect()
+}
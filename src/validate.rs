@@ -0,0 +1,402 @@
+//! `election validate YEAR|DIR`: check a data directory for the problems that
+//! would otherwise make an analysis fail outright or quietly go wrong.
+
+use crate::config::Settings;
+use crate::jurisdiction::{self, Federal, Jurisdiction};
+use crate::{data_dir, summary, transposed, Party, Poll};
+use clap::Clap;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Error, Read};
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+
+/// The columns that every poll-by-poll file must have. The French downloads
+/// give the same headers with their halves swapped.
+const REQUIRED: &[&str] = &[
+    "Electoral District Number/Numéro de circonscription",
+    "Electoral District Name_English/Nom de circonscription_Anglais",
+    "Political Affiliation Name_English/Appartenance politique_Anglais",
+    "Candidate’s Family Name/Nom de famille du candidat",
+    "Candidate’s First Name/Prénom du candidat",
+    "Candidate Poll Votes Count/Votes du candidat pour le bureau",
+];
+
+const PARTY: &str = "Political Affiliation Name_English/Appartenance politique_Anglais";
+
+/// Polls merged into another have their votes counted there instead.
+const MERGED: &str = "Merge With/Fusionné avec";
+
+/// Everything found wrong with a data directory.
+#[derive(Serialize, JsonSchema)]
+pub struct Report {
+    dir: PathBuf,
+    files: usize,
+    rows: usize,
+    ridings: RidingCount,
+    unexpected_headers: Vec<HeaderProblem>,
+    malformed_rows: Vec<RowProblem>,
+    /// Party labels that no known party goes by, and how often each appears.
+    unknown_parties: BTreeMap<String, usize>,
+    /// Polls that were held, yet had no votes for anyone.
+    zero_vote_polls: Vec<PollId>,
+    /// Candidates who received no votes at all.
+    zero_vote_candidates: Vec<CandidateId>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RidingCount {
+    /// Only known when the directory is named after the election's year.
+    expected: Option<usize>,
+    found: usize,
+    missing: Option<usize>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct HeaderProblem {
+    file: String,
+    missing: Vec<&'static str>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RowProblem {
+    file: String,
+    line: Option<u64>,
+    error: String,
+}
+
+#[derive(Serialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord)]
+struct PollId {
+    district: u32,
+    riding: String,
+    poll: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CandidateId {
+    district: u32,
+    riding: String,
+    party: Party,
+    last_name: String,
+    first_name: String,
+}
+
+/// The votes of the polls and candidates seen so far.
+#[derive(Default)]
+struct Tally {
+    polls: BTreeMap<PollId, usize>,
+    candidates: BTreeMap<(u32, String, Party, String, String), usize>,
+    districts: BTreeSet<u32>,
+}
+
+/// How many ridings each election had.
+fn expected_ridings(year: usize) -> Option<usize> {
+    match year {
+        2004 | 2006 | 2008 | 2011 => Some(308),
+        2015 | 2019 | 2021 => Some(338),
+        _ => None,
+    }
+}
+
+#[derive(Clap, Debug)]
+pub struct ValidateArgs {
+    /// A year, to check its data directory, or the path of any directory.
+    #[clap(value_name = "YEAR|DIR")]
+    data: String,
+}
+
+impl ValidateArgs {
+    /// The data directory of the year, or the directory, given.
+    pub fn dir(&self, settings: &Settings) -> PathBuf {
+        match self.data.parse::<usize>() {
+            Ok(year) => data_dir(settings, year),
+            Err(_) => PathBuf::from(&self.data),
+        }
+    }
+}
+
+/// Check the data directory of the year, or the directory, given in `args`.
+pub fn validate(settings: &Settings, args: &ValidateArgs) -> Result<Report, Error> {
+    let dir = args.dir(settings);
+    // Transposed results are on the boundaries of a later year's election.
+    let transposed = dir
+        .parent()
+        .and_then(|p| p.file_name())
+        .is_some_and(|n| n == "transposed");
+    let year = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.parse().ok())
+        .filter(|_| transposed.not());
+
+    let mut report = Report {
+        dir: dir.clone(),
+        files: 0,
+        rows: 0,
+        ridings: RidingCount {
+            expected: year.and_then(expected_ridings),
+            found: 0,
+            missing: None,
+        },
+        unexpected_headers: Vec::new(),
+        malformed_rows: Vec::new(),
+        unknown_parties: BTreeMap::new(),
+        zero_vote_polls: Vec::new(),
+        zero_vote_candidates: Vec::new(),
+    };
+    let mut tally = Tally::default();
+
+    for path in files(&dir)? {
+        let name = path.display().to_string();
+
+        if path.extension().is_some_and(|e| e == "zip") {
+            let mut zip = zip::ZipArchive::new(std::fs::File::open(&path)?)?;
+
+            for i in 0..zip.len() {
+                let entry = zip.by_index(i)?;
+
+                if entry.name().ends_with(".csv") {
+                    let name = format!("{}:{}", name, entry.name());
+                    let reader = csv::Reader::from_reader(entry);
+                    check(&name, reader, &mut report, &mut tally)?;
+                }
+            }
+        } else if path.extension().is_some_and(|e| e == "csv") {
+            let reader = csv::Reader::from_path(&path)?;
+            check(&name, reader, &mut report, &mut tally)?;
+        }
+    }
+
+    report.ridings.found = tally.districts.len();
+    report.ridings.missing = report
+        .ridings
+        .expected
+        .map(|e| e.saturating_sub(tally.districts.len()));
+    report.zero_vote_polls = tally
+        .polls
+        .into_iter()
+        .filter(|(_, votes)| *votes == 0)
+        .map(|(id, _)| id)
+        .collect();
+    report.zero_vote_candidates = tally
+        .candidates
+        .into_iter()
+        .filter(|(_, votes)| *votes == 0)
+        .map(
+            |((district, riding, party, last_name, first_name), _)| CandidateId {
+                district,
+                riding,
+                party,
+                last_name,
+                first_name,
+            },
+        )
+        .collect();
+
+    Ok(report)
+}
+
+/// The files of a directory, in a stable order.
+fn files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|de| de.ok())
+        .map(|de| de.path())
+        .collect();
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Check the headers and every row of one CSV.
+fn check<R: Read>(
+    name: &str,
+    mut reader: csv::Reader<R>,
+    report: &mut Report,
+    tally: &mut Tally,
+) -> Result<(), Error> {
+    report.files += 1;
+    let headers = reader.headers()?.clone();
+
+    // Summary tables and transposed results are read all at once, so only the
+    // first problem in them can be found.
+    if summary::is_summary(&headers) || transposed::is_transposed(&headers) {
+        let result = if summary::is_summary(&headers) {
+            summary::read(reader)
+        } else {
+            transposed::read(reader)
+        };
+
+        match result {
+            Ok(polls) => {
+                report.rows += polls.len();
+                polls.iter().for_each(|p| tally.candidate(p));
+            }
+            Err(e) => report.malformed_rows.push(RowProblem {
+                file: name.to_string(),
+                line: None,
+                error: e.to_string(),
+            }),
+        }
+        return Ok(());
+    }
+
+    if let Some(jurisdiction) = jurisdiction::provincial(&headers) {
+        return check_provincial(name, jurisdiction, reader, report, tally);
+    }
+
+    let canonical: Vec<String> = headers.iter().map(canonical).collect();
+    let missing: Vec<_> = REQUIRED
+        .iter()
+        .filter(|r| canonical.iter().any(|h| h == *r).not())
+        .copied()
+        .collect();
+
+    if missing.is_empty().not() {
+        report.unexpected_headers.push(HeaderProblem {
+            file: name.to_string(),
+            missing,
+        });
+        return Ok(());
+    }
+
+    let column = |wanted: &str| canonical.iter().position(|h| h == wanted);
+    let party = column(PARTY);
+    let merged = column(MERGED);
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                report.malformed_rows.push(RowProblem {
+                    file: name.to_string(),
+                    line: e.position().map(|p| p.line()),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        // Repeated headers, as in concatenated files, are skipped on reading.
+        if record == headers {
+            continue;
+        }
+        report.rows += 1;
+
+        let label = party.and_then(|i| record.get(i)).unwrap_or_default();
+        if known_party(label).not() {
+            *report.unknown_parties.entry(label.to_string()).or_default() += 1;
+            continue;
+        }
+
+        match record.deserialize::<Poll>(Some(&headers)) {
+            Err(e) => report.malformed_rows.push(RowProblem {
+                file: name.to_string(),
+                line: record.position().map(|p| p.line()),
+                error: e.to_string(),
+            }),
+            Ok(poll) => {
+                let was_merged = merged
+                    .and_then(|i| record.get(i))
+                    .is_some_and(|m| m.trim().is_empty().not());
+
+                if poll.void.not() && poll.not_held.not() && was_merged.not() {
+                    tally.poll(&poll);
+                }
+                tally.candidate(&poll);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check every row of one CSV of a province's results, which has no columns
+/// beyond those its schema reads.
+fn check_provincial<R: Read>(
+    name: &str,
+    jurisdiction: &dyn Jurisdiction,
+    mut reader: csv::Reader<R>,
+    report: &mut Report,
+    tally: &mut Tally,
+) -> Result<(), Error> {
+    let headers = reader.headers()?.clone();
+    // Every province has one.
+    let schema = match jurisdiction.schema() {
+        Some(schema) => schema,
+        None => return Ok(()),
+    };
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) if record == headers => continue,
+            Ok(record) => record,
+            Err(e) => {
+                report.malformed_rows.push(RowProblem {
+                    file: name.to_string(),
+                    line: e.position().map(|p| p.line()),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        report.rows += 1;
+
+        let label = schema.label(&headers, &record);
+        if jurisdiction.party(label).is_none() {
+            *report.unknown_parties.entry(label.to_string()).or_default() += 1;
+        }
+
+        match jurisdiction.poll(&headers, &record) {
+            Err(e) => report.malformed_rows.push(RowProblem {
+                file: name.to_string(),
+                line: record.position().map(|p| p.line()),
+                error: e.to_string(),
+            }),
+            Ok(poll) => {
+                tally.poll(&poll);
+                tally.candidate(&poll);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Tally {
+    fn poll(&mut self, poll: &Poll) {
+        let id = PollId {
+            district: poll.district,
+            riding: poll.riding.clone(),
+            poll: poll.number.clone(),
+        };
+        *self.polls.entry(id).or_default() += poll.votes;
+    }
+
+    fn candidate(&mut self, poll: &Poll) {
+        let id = (
+            poll.district,
+            poll.riding.clone(),
+            poll.party.clone(),
+            poll.last_name.clone(),
+            poll.first_name.clone(),
+        );
+        *self.candidates.entry(id).or_default() += poll.votes;
+        self.districts.insert(poll.district);
+    }
+}
+
+/// A header as the English files write it.
+fn canonical(header: &str) -> String {
+    let header = header.replace('\'', "’");
+    let known = |h: &str| REQUIRED.contains(&h) || h == MERGED;
+
+    match header.split_once('/').map(|(a, b)| format!("{}/{}", b, a)) {
+        Some(swapped) if known(&swapped) => swapped,
+        _ => header,
+    }
+}
+
+fn known_party(label: &str) -> bool {
+    Federal.party(label).is_some()
+}